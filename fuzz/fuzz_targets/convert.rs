@@ -0,0 +1,10 @@
+//! Any input, parser has to fail on it gracefully instead of panicking or running out of memory
+
+#![no_main]
+
+use hc_multitool::converter;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = converter::convert(&mut &data[..]);
+});
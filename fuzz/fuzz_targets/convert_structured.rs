@@ -0,0 +1,30 @@
+//! Saves of the right structure, with the rest of the input appended or flipping their bytes
+//!
+//! Gets deeper into the parser than random bytes do, since every value before the broken one is valid
+
+#![no_main]
+
+use hc_multitool::converter;
+use hc_multitool::fuzz::SaveGen;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let mut save = SaveGen::from_bytes(data).binary_save();
+
+    assert!(converter::convert(&mut &save[..]).is_ok(), "Failed to convert a valid save");
+
+    let Some((&how, rest)) = data.get(8..).and_then(<[u8]>::split_first) else {
+        return;
+    };
+
+    if how % 2 == 0 {
+        save.extend(rest);
+    } else {
+        for pair in rest.chunks_exact(2) {
+            let len = save.len();
+            save[pair[0] as usize % len] ^= pair[1];
+        }
+    }
+
+    let _ = converter::convert(&mut &save[..]);
+});
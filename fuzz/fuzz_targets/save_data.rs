@@ -0,0 +1,16 @@
+//! Generated JSON saves have to be read into the model and written back the same as they were
+
+#![no_main]
+
+use hc_multitool::fuzz::SaveGen;
+use hc_multitool::save::SaveData;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let save = SaveGen::from_bytes(data).json_save();
+    let save_data = &save["save_data_key"];
+
+    let parsed: SaveData = serde_json::from_value(save_data.clone()).expect("Failed to read a valid save");
+
+    assert_eq!(&serde_json::to_value(parsed).unwrap(), save_data);
+});
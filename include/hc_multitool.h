@@ -0,0 +1,28 @@
+/* C interface of hc_multitool, built with the `ffi` feature */
+
+#ifndef HC_MULTITOOL_H
+#define HC_MULTITOOL_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/*
+ * Convert an old binary save (contents of `savegame.bin` and the like) into the JSON the release uses.
+ *
+ * Returns the JSON as a NUL-terminated string, or NULL if it failed. On failure, if `error` isn't NULL, it's set to
+ * the error message. Both have to be released with `hc_string_free`.
+ */
+char *hc_convert_bin_to_json(const uint8_t *data, size_t len, char **error);
+
+/* Release a string returned by the library. Does nothing for NULL */
+void hc_string_free(char *s);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* HC_MULTITOOL_H */
@@ -0,0 +1,199 @@
+use clap::{Args, Subcommand};
+use eyre::{Context, ContextCompat, Result as EResult};
+use serde_json::json;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::exit::Failure;
+use crate::i18n::tr;
+use crate::known;
+use crate::organiser::FURN_FIXED;
+use crate::save::{FurnitureItem, SaveFile, COSMETICS_LISTS};
+use crate::utils::{self, JObj, SaveDirHandler};
+
+#[derive(Args)]
+#[derive(Debug)]
+pub struct Ops {
+    #[command(subcommand)]
+    action: Cmd,
+}
+
+#[derive(Subcommand)]
+#[derive(Debug)]
+enum Cmd {
+    /// Shuffle which wardrobe items and furniture the save owns, for a randomizer-style playthrough
+    ///
+    /// Amount of items in each category stays the same, worn clothes and the computer and journal are kept.
+    /// Original lists are recorded next to the save, so they can be put back with `--restore`
+    Randomize {
+        /// Save slot number (0-3)
+        save_slot: u8,
+        /// Seed to shuffle with, the same seed always gives the same result. Random if not specified
+        #[arg(long, conflicts_with = "restore")]
+        seed: Option<u64>,
+        /// Put back the lists recorded before randomizing
+        #[arg(long)]
+        restore: bool,
+    },
+}
+
+pub fn handler(ops: Ops, mut save_dir: SaveDirHandler) -> EResult<()> {
+    log::info!("Cheating");
+
+    match ops.action {
+        Cmd::Randomize { save_slot, restore: false, seed } => {
+            randomize(&mut save_dir, save_slot, seed).context("Failed to randomize the save")?
+        }
+        Cmd::Randomize { save_slot, restore: true, .. } => {
+            restore_randomized(&mut save_dir, save_slot).context("Failed to restore the save")?
+        }
+    }
+
+    Ok(())
+}
+
+fn randomize(save_dir: &mut SaveDirHandler, save_slot: u8, seed: Option<u64>) -> EResult<()> {
+    require_complete_items()?;
+
+    let mut save = SaveFile::open(save_dir, save_slot)?;
+    let record_path = utils::with_added_extension(&save.path, "randomizer");
+
+    if utils::file_exists(&record_path) {
+        return Err(Failure::WriteRefused.error("Save is already randomized, restore it first"));
+    }
+
+    if !utils::confirm(&tr!("confirm-randomize", slot = save_slot))? {
+        return Err(Failure::WriteRefused.error("Cancelled"));
+    }
+
+    let seed = seed.unwrap_or_else(|| {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_nanos() as u64)
+    });
+    let mut rng = SplitMix64(seed);
+
+    log::info!("Randomizing with seed {seed}");
+
+    let mut save_data = save.typed_data()?;
+    let mut record = JObj::new();
+    let worn = save_data.worn().map(String::from);
+
+    for (((name, _), owned), worn) in COSMETICS_LISTS.into_iter().zip(save_data.cosmetics_mut()).zip(worn) {
+        // owned items are all in the pool, duplicates included, so there are always enough to draw from
+        let mut pool: Vec<String> = owned.clone();
+        if let Some(i) = pool.iter().position(|i| *i == worn) {
+            pool.remove(i);
+        }
+        pool.extend(known::cosmetics(name).iter().filter(|i| !owned.contains(&i.to_string())).map(|i| i.to_string()));
+        rng.shuffle(&mut pool);
+
+        // keep the worn item, so the character doesn't end up wearing something they don't own
+        let randomized = match owned.len() {
+            0 => Vec::new(),
+            len => [worn].into_iter().chain(pool.into_iter().take(len - 1)).collect(),
+        };
+
+        record.insert(name.to_string(), json!(owned));
+        *owned = randomized;
+    }
+
+    let is_shuffled = |item: &FurnitureItem| !FURN_FIXED.contains(&item.name.as_str());
+
+    // items being shuffled are drawn from along with the rest of the known ones, so none is drawn twice
+    let mut pool: Vec<String> = save_data.furniture.iter().filter(|i| is_shuffled(i)).map(|i| i.name.clone()).collect();
+    pool.extend(
+        known::FURNITURE
+            .iter()
+            .filter(|(name, _)| !FURN_FIXED.contains(name) && !save_data.furniture.iter().any(|i| i.name == *name))
+            .map(|(name, _)| name.to_string()),
+    );
+    rng.shuffle(&mut pool);
+
+    record.insert("furnlist".to_string(), json!(save_data.furniture));
+
+    // only the name changes, whatever else the game keeps for the item stays with it
+    let mut drawn = pool.into_iter();
+    for item in save_data.furniture.iter_mut().filter(|i| is_shuffled(i)) {
+        if let Some(name) = drawn.next() {
+            item.name = name;
+        }
+    }
+
+    save.set_typed_data(save_data)?;
+    save.write()?;
+
+    // only recorded once the save is written, so a failed write doesn't leave the save looking randomized
+    utils::write_json_file(&record_path, &record).context("Failed to record original lists")?;
+    log::info!("Recorded original lists to {}", record_path.display());
+
+    log::info!("Finished randomizing");
+
+    Ok(())
+}
+
+/// Shuffling only makes sense once the known items have every item of the game, otherwise it'd draw from a part of them
+fn require_complete_items() -> EResult<()> {
+    if known::ITEMS_COMPLETE {
+        return Ok(());
+    }
+
+    Err(Failure::NotFound
+        .details("The Multitool doesn't know every item of the game yet")
+        .suggestion("Randomizing will be available once it does")
+        .into())
+}
+
+fn restore_randomized(save_dir: &mut SaveDirHandler, save_slot: u8) -> EResult<()> {
+    let mut save = SaveFile::open(save_dir, save_slot)?;
+    let record_path = utils::with_added_extension(&save.path, "randomizer");
+
+    if !utils::file_exists(&record_path) {
+        return Err(Failure::WriteRefused.error("Save wasn't randomized, nothing to restore"));
+    }
+
+    let record = utils::read_json_file(&record_path).context("Failed to read recorded lists")?;
+    let record = record.as_object().context("Invalid record: not a JSON object")?;
+
+    log::warn!("Any items acquired since randomizing will be lost");
+
+    if !utils::confirm(&tr!("confirm-restore-randomized", slot = save_slot))? {
+        return Err(Failure::WriteRefused.error("Cancelled"));
+    }
+
+    let save_data = save.data_mut()?;
+    for (name, list) in record {
+        log::info!("Restoring {name}");
+        save_data.insert(name.clone(), list.clone());
+    }
+
+    save.write()?;
+    utils::remove_file(&record_path).context("Failed to remove recorded lists")?;
+
+    log::info!("Finished restoring");
+
+    Ok(())
+}
+
+/// Small seeded generator, so the same seed gives the same result on every platform and version
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.below(i + 1));
+        }
+    }
+}
@@ -0,0 +1,309 @@
+use clap::error::ErrorKind;
+use clap::{ArgAction, ColorChoice, CommandFactory, Parser, Subcommand};
+use color_eyre::config::{HookBuilder, Theme};
+use color_eyre::eyre::Result as CEResult;
+use env_logger::{Target, WriteStyle};
+use std::env;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::sync::Arc;
+
+use crate::config::Config;
+use crate::utils::SaveDirHandler;
+use crate::vfs::{self, MemoryFs, RealFs};
+use crate::{
+    cheat, completions, config, converter, doctor, emails, exit, furniture, i18n, log_file, ngplus, open, organiser,
+    outfits, output, paths, progress, repl, rpc, run, self_update, serve, settings, timings, utils, wardrobe,
+};
+
+#[cfg(debug_assertions)]
+const LOGGING_LEVEL: &str = "info,hc_multitool";
+#[cfg(not(debug_assertions))]
+const LOGGING_LEVEL: &str = "info";
+
+/// Entry point of the `hc_multitool` executable
+pub fn main() -> ExitCode {
+    let config = match Config::load() {
+        Ok(config) => config,
+        Err(report) => {
+            exit::print(&report, exit::ErrorFormat::Text);
+
+            return exit::code(&report);
+        }
+    };
+
+    let cli = Cli::parse_from(config::expand_aliases(env::args_os().collect(), &config));
+
+    match (&cli.action, cli.rpc) {
+        (None, false) => Cli::command().error(ErrorKind::MissingSubcommand, "No command given").exit(),
+        (Some(_), true) => {
+            Cli::command().error(ErrorKind::ArgumentConflict, "`--rpc` can't be used with a command").exit()
+        }
+        _ => {}
+    }
+    let error_format = cli.error_format;
+
+    match run(cli, config) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(report) => {
+            exit::print(&report, error_format);
+
+            exit::code(&report)
+        }
+    }
+}
+
+fn run(cli: Cli, config: Config) -> CEResult<()> {
+    let color = output::use_color(cli.color);
+    let mut logger = env_logger::Builder::new();
+
+    match cli.log_filter() {
+        Some(filter) => logger.parse_filters(filter),
+        None => logger.parse_env(env_logger::Env::default().default_filter_or(LOGGING_LEVEL)),
+    };
+
+    if let Some(path) = cli.log_file.as_ref().or(config.log_file.as_ref()) {
+        logger.target(Target::Pipe(Box::new(log_file::Mirror::open(path)?)));
+    }
+
+    logger
+        .write_style(if color { WriteStyle::Always } else { WriteStyle::Never })
+        .init();
+
+    HookBuilder::default()
+        .theme(if color { Theme::dark() } else { Theme::new() })
+        .install()?;
+
+    log::debug!("Parsed args: {cli:?}");
+
+    i18n::init(config.language.as_deref());
+
+    let save_dir = SaveDirHandler::new_override(cli.save_dir);
+    output::set_format(cli.format);
+    // there is nobody to ask in RPC mode, stdin is taken by the requests
+    utils::set_assume_yes(cli.yes || cli.rpc);
+    timings::enable(cli.timings);
+
+    let dry_run = cli.dry_run.then(|| Arc::new(MemoryFs::over(RealFs)));
+
+    if let Some(fs) = &dry_run {
+        vfs::set(fs.clone());
+    }
+
+    let result = match cli.action {
+        Some(action) => dispatch(action, save_dir),
+        None => rpc::handler(save_dir),
+    };
+    timings::report();
+
+    if let Some(fs) = dry_run {
+        report_dry_run(&fs);
+    }
+
+    result?;
+
+    log::debug!("Exiting");
+
+    Ok(())
+}
+
+/// Log what would have been changed, if not for `--dry-run`
+fn report_dry_run(fs: &MemoryFs) {
+    let written = fs.written();
+    let removed = fs.removed();
+
+    if written.is_empty() && removed.is_empty() {
+        log::info!("Dry run: no files would have been changed");
+    }
+
+    for path in written {
+        log::info!("Dry run: would have written {}", path.display());
+    }
+
+    for path in removed {
+        log::info!("Dry run: would have removed {}", path.display());
+    }
+}
+
+pub(crate) fn dispatch(action: Command, save_dir: SaveDirHandler) -> CEResult<()> {
+    let _timer = timings::stage("Command");
+
+    match action {
+        Command::Convert(ops) => converter::handler(ops),
+        Command::Organise(ops) => organiser::handler(ops, save_dir),
+        Command::Outfits(ops) => outfits::handler(ops, save_dir),
+        Command::Emails(ops) => emails::handler(ops, save_dir),
+        Command::Furniture(ops) => furniture::handler(ops, save_dir),
+        Command::Progress(ops) => progress::handler(ops, save_dir),
+        Command::Settings(ops) => settings::handler(ops),
+        Command::Ngplus(ops) => ngplus::handler(ops, save_dir),
+        Command::Cheat(ops) => cheat::handler(ops, save_dir),
+        Command::Wardrobe(ops) => wardrobe::handler(ops, save_dir),
+        Command::Run(ops) => run::handler(ops, save_dir),
+        Command::Repl(ops) => repl::handler(ops, save_dir),
+        Command::Serve(ops) => serve::handler(ops, save_dir),
+        Command::Open(ops) => open::handler(ops, save_dir),
+        Command::SelfUpdate(ops) => self_update::handler(ops),
+        Command::Doctor(ops) => doctor::handler(ops, save_dir),
+        Command::Paths(ops) => paths::handler(ops, save_dir),
+        Command::Completions(ops) => completions::handler(ops),
+        Command::Complete(ops) => completions::complete_handler(ops),
+    }
+}
+
+#[derive(Parser)]
+#[derive(Debug)]
+pub(crate) struct Cli {
+    #[command(subcommand)]
+    action: Option<Command>,
+    /// Answer JSON-RPC requests on stdin, one per line, instead of running a command
+    ///
+    /// For editors and GUI wrappers that keep the Multitool running. See the README for the methods
+    #[arg(long)]
+    rpc: bool,
+    /// Override for the save data direcotry
+    ///
+    /// If not specified - application will attempt to locate it automatically
+    #[arg(long, global = true)]
+    save_dir: Option<PathBuf>,
+    /// Format of the output for commands that list or show things
+    #[arg(long, global = true, value_enum, default_value_t)]
+    format: output::Format,
+    /// Don't ask for confirmation before overwriting or deleting anything
+    ///
+    /// Confirmation is only asked for when running in a terminal in the first place
+    #[arg(short = 'y', long, global = true)]
+    yes: bool,
+    /// Format in which errors are printed
+    #[arg(long, global = true, value_enum, default_value_t)]
+    error_format: exit::ErrorFormat,
+    /// Only print errors
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    quiet: bool,
+    /// Print more details of what is being done, repeat (`-vv`) for even more
+    ///
+    /// Both this and `--quiet` take priority over `RUST_LOG`
+    #[arg(short, long, global = true, action = ArgAction::Count)]
+    verbose: u8,
+    /// When to use colors in the output
+    ///
+    /// With `auto` colors are used only in a terminal, and not at all if `NO_COLOR` environment variable is set
+    #[arg(long, global = true, value_name = "WHEN", default_value_t)]
+    color: ColorChoice,
+    /// Also write the logs into this file
+    ///
+    /// File is appended to, and rotated once it grows over 1 MiB
+    #[arg(long, global = true, value_name = "PATH")]
+    log_file: Option<PathBuf>,
+    /// Print how long each stage (reading, parsing, the command itself, writing) took, once finished
+    #[arg(long, global = true)]
+    timings: bool,
+    /// Don't change any files, only report which ones would have been written or removed
+    ///
+    /// Commands run as usual otherwise, seeing their own changes as if they were made
+    #[arg(long, global = true)]
+    dry_run: bool,
+}
+
+impl Cli {
+    /// Logging filter requested by the verbosity flags, if any
+    fn log_filter(&self) -> Option<&'static str> {
+        match (self.quiet, self.verbose) {
+            (true, _) => Some("error"),
+            (false, 0) => None,
+            (false, 1) => Some("debug"),
+            (false, _) => Some("trace"),
+        }
+    }
+}
+
+#[derive(Subcommand)]
+#[derive(Debug)]
+pub(crate) enum Command {
+    /// Convert older pre-release save (.bin) to release version (.json)
+    Convert(converter::Ops),
+    /// Organise various messes inside of the save file
+    ///
+    /// Such as:
+    ///
+    /// - Sort equpable lists
+    /// - Sort the furniture list
+    /// - Deduplicate emails
+    #[command(verbatim_doc_comment)]
+    Organise(organiser::Ops),
+    /// Manage outfits
+    ///
+    /// By default outfits will be stored in the `outfits.json` file next to the saves.
+    /// "Name" of the outfit is the JSON key in that file.
+    ///
+    /// As game doesn't allow empty slots, `save` command will save each equiped item, however you can edit the outfit
+    /// in the file by hand to remove any parts you don't want, in which case `load`-ing such outfit will only apply
+    /// the pieces still left in
+    Outfits(outfits::Ops),
+    /// Manage emails
+    ///
+    /// Emails are referred to by their in-game ID
+    Emails(emails::Ops),
+    /// Manage furniture
+    ///
+    /// Items are referred to either by their name or by their position (`#`) in the list
+    Furniture(furniture::Ops),
+    /// Report progress of the save
+    Progress(progress::Ops),
+    /// Show or change game settings
+    ///
+    /// Unlike the other commands this works with the game's own settings file, rather than a save slot.
+    /// Handy when a bad setting prevents the game from starting
+    Settings(settings::Ops),
+    /// Carry things over from one save into a new game
+    ///
+    /// Start a new game in the target slot first, then use this to bring over whatever should be kept.
+    /// Nothing is removed from the target save, only added
+    Ngplus(ngplus::Ops),
+    /// Change what the save owns without earning it
+    ///
+    /// Each of these asks for confirmation first
+    Cheat(cheat::Ops),
+    /// Work with owned wardrobe items
+    Wardrobe(wardrobe::Ops),
+    /// Run a script of commands, one per line, as a single change
+    ///
+    /// Each line is a command without the `hc_multitool` part, e.g. `emails mark-read 0 12`. Empty lines and ones
+    /// starting with `#` are ignored. Files are only read once, and nothing is written until every command
+    /// succeeded - if any of them fails no changes are made at all
+    Run(run::Ops),
+    /// Explore and edit the raw contents of a save interactively
+    ///
+    /// Changes are kept in memory until written with `:w`. Type `help` inside for the list of commands
+    Repl(repl::Ops),
+    /// Serve a small HTTP API, for overlays and other tools to work with the saves
+    ///
+    /// Requests are handled one at a time, until the Multitool is stopped. See the README for the endpoints
+    Serve(serve::Ops),
+    /// Open the save directory or one of the files the Multitool uses
+    ///
+    /// Directories are opened in the file manager, files in `$VISUAL` or `$EDITOR` (or the system's default
+    /// application for them, if neither is set)
+    Open(open::Ops),
+    /// Check whether there is a newer release of the Multitool on GitHub
+    ///
+    /// Doesn't install it yet, the new version has to be downloaded from the release page linked in the output
+    SelfUpdate(self_update::Ops),
+    /// Check for common problems with the saves and the Multitool's setup
+    ///
+    /// Such as the save dir not being found, broken saves, backups or outfits, or the game running
+    /// while the saves are being changed
+    Doctor(doctor::Ops),
+    /// Print where the Multitool looks for the saves and its files
+    ///
+    /// Custom paths given to other commands (like `--outfits-path`) aren't taken into account
+    Paths(paths::Ops),
+    /// Generate shell completion script
+    ///
+    /// Prints the script for the specified shell to stdout, for example:
+    /// `hc_multitool completions bash > /usr/share/bash-completion/completions/hc_multitool`
+    Completions(completions::Ops),
+    /// Values for the shell completions, used by the scripts from `completions`
+    #[command(name = "__complete", hide = true)]
+    Complete(completions::CompleteOps),
+}
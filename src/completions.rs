@@ -0,0 +1,177 @@
+use clap::{Args, CommandFactory};
+use clap_complete::Shell;
+use eyre::Result as EResult;
+use std::path::PathBuf;
+
+use crate::exit::Failure;
+use crate::organiser;
+use crate::outfits::{self, OUTFITS_FILE};
+use crate::utils::{self, SaveDirHandler};
+use crate::cli::Cli;
+
+#[derive(Args)]
+#[derive(Debug)]
+pub struct Ops {
+    /// Shell to generate the completions for
+    shell: Shell,
+}
+
+pub fn handler(ops: Ops) -> EResult<()> {
+    log::debug!("Generating completions for {}", ops.shell);
+
+    let mut cmd = Cli::command();
+    let name = cmd.get_name().to_string();
+
+    clap_complete::generate(ops.shell, &mut cmd, &name, &mut std::io::stdout());
+
+    match ops.shell {
+        Shell::Bash => print!("{}", BASH_DYNAMIC.replace("{name}", &name)),
+        Shell::Fish => print!("{}", FISH_DYNAMIC.replace("{name}", &name)),
+        shell => log::debug!("No dynamic completions for {shell}"),
+    }
+
+    Ok(())
+}
+
+/// Wraps the generated completion function, asking the Multitool for values before falling back to it
+const BASH_DYNAMIC: &str = r#"
+_{name}_dynamic() {
+    local values
+    if values="$("${COMP_WORDS[0]}" __complete "$((COMP_CWORD - 1))" -- "${COMP_WORDS[@]:1}" 2>/dev/null)"; then
+        # values can have spaces in them, but are one per line
+        local IFS=$'\n'
+        COMPREPLY=($(compgen -W "${values}" -- "${COMP_WORDS[COMP_CWORD]}"))
+        return 0
+    fi
+    _{name} "$@"
+}
+
+complete -F _{name}_dynamic -o nosort -o bashdefault -o default {name}
+"#;
+
+const FISH_DYNAMIC: &str = r#"
+function __{name}_dynamic
+    set -l words (commandline -opc)
+    set -e words[1]
+    {name} __complete (count $words) -- $words (commandline -ct) 2>/dev/null
+end
+
+complete -c {name} -f -n '__{name}_dynamic >/dev/null' -a '(__{name}_dynamic)'
+"#;
+
+#[derive(Args)]
+#[derive(Debug)]
+pub struct CompleteOps {
+    /// Position of the word being completed, among the arguments
+    index: usize,
+    /// Arguments typed so far (without the executable)
+    #[arg(last = true)]
+    words: Vec<String>,
+}
+
+/// Print the values that fit the word being completed, one per line
+///
+/// Fails if the word isn't something that can be completed dynamically, so the shell can fall back to the
+/// generated completions
+pub fn complete_handler(ops: CompleteOps) -> EResult<()> {
+    let before = &ops.words[..ops.index.min(ops.words.len())];
+    let (value, save_dir) = completed_value(before);
+
+    log::debug!("Completing {value:?}");
+
+    let mut save_dir = SaveDirHandler::new_override(save_dir);
+
+    let mut values = match value {
+        Some(Value::Slot) => organiser::existing_slots(&mut save_dir)?.iter().map(u8::to_string).collect(),
+        Some(Value::Backup) => {
+            let mut slots = Vec::new();
+
+            for slot in organiser::existing_slots(&mut save_dir)? {
+                if utils::file_exists(&utils::with_added_extension(&save_dir.resolve_save_slot(slot)?, "bak")) {
+                    slots.push(slot.to_string());
+                }
+            }
+
+            slots
+        }
+        Some(Value::Outfit) => outfits::outfit_names(&save_dir.resolve_file(OUTFITS_FILE)?)?,
+        None => return Err(Failure::NotFound.error("Nothing to complete")),
+    };
+
+    values.sort();
+    values.iter().for_each(|value| println!("{value}"));
+
+    Ok(())
+}
+
+/// Kind of value that comes after the words, along with `--save-dir` if it's among them
+fn completed_value(words: &[String]) -> (Option<Value>, Option<PathBuf>) {
+    let mut cmd = Cli::command();
+    cmd.build();
+
+    let mut positionals = 0;
+    let mut save_dir = None;
+    let mut option_value: Option<String> = None;
+    let mut words = words.iter();
+
+    while let Some(word) = words.next() {
+        option_value = None;
+
+        let option = match word.strip_prefix("--") {
+            Some(long) if !long.is_empty() && !long.contains('=') => {
+                cmd.get_arguments().find(|a| a.get_long() == Some(long))
+            }
+            Some(_) => continue,
+            None if word.len() == 2 && word.starts_with('-') => {
+                cmd.get_arguments().find(|a| a.get_short().is_some_and(|s| word.ends_with(s)))
+            }
+            None if word.starts_with('-') => continue,
+            None => {
+                match cmd.find_subcommand(word) {
+                    Some(sub) => {
+                        cmd = sub.clone();
+                        positionals = 0;
+                    }
+                    None => positionals += 1,
+                }
+
+                continue;
+            }
+        };
+
+        let Some(option) = option.filter(|o| o.get_action().takes_values()) else {
+            continue;
+        };
+
+        match words.next() {
+            Some(value) if option.get_id() == "save_dir" => save_dir = Some(PathBuf::from(value)),
+            Some(_) => {}
+            None => option_value = Some(option.get_id().to_string()),
+        }
+    }
+
+    let id = match option_value {
+        Some(id) => id,
+        None => match cmd.get_positionals().nth(positionals) {
+            Some(arg) => arg.get_id().to_string(),
+            None => return (None, save_dir),
+        },
+    };
+
+    let value = match id.as_str() {
+        "save_slot" if cmd.get_name() == "backups" => Some(Value::Backup),
+        "save_slot" | "into" => Some(Value::Slot),
+        "outfit" => Some(Value::Outfit),
+        _ => None,
+    };
+
+    (value, save_dir)
+}
+
+#[derive(Debug)]
+enum Value {
+    Slot,
+    /// Slot that has a backup
+    Backup,
+    Outfit,
+}
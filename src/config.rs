@@ -0,0 +1,98 @@
+use clap::CommandFactory;
+use eyre::{Context, Result as EResult};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::path::PathBuf;
+
+use crate::utils;
+use crate::cli::Cli;
+
+/// User's configuration of the Multitool
+#[derive(Deserialize, Default)]
+#[derive(Debug)]
+#[serde(default)]
+pub struct Config {
+    /// Short forms of commands, e.g. `"o": "outfits load 0"`
+    pub aliases: HashMap<String, String>,
+    /// Language of the printed text, taken from the environment if not specified
+    pub language: Option<String>,
+    /// File to mirror the logs into, unless `--log-file` is specified
+    pub log_file: Option<PathBuf>,
+}
+
+impl Config {
+    pub fn path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("hc_multitool/config.json"))
+    }
+
+    /// Read the config file, if there is one
+    pub fn load() -> EResult<Self> {
+        let Some(path) = Self::path().filter(|p| utils::file_exists(p)) else {
+            return Ok(Self::default());
+        };
+
+        let json = utils::read_json_file(&path).context("Failed to read config file")?;
+
+        serde_json::from_value(json).with_context(|| format!("Invalid config file {}", path.display()))
+    }
+}
+
+/// Replace the alias in the command line arguments with the command it stands for
+///
+/// Only the command itself (first argument that isn't an option) can be an alias, and aliases can't shadow
+/// the built-in commands
+pub fn expand_aliases(args: Vec<OsString>, config: &Config) -> Vec<OsString> {
+    let Some(pos) = command_position(&args) else {
+        return args;
+    };
+
+    let Some(name) = args[pos].to_str() else {
+        return args;
+    };
+
+    let Some(expansion) = config.aliases.get(name) else {
+        return args;
+    };
+
+    if Cli::command().find_subcommand(name).is_some() {
+        eprintln!("Alias \"{name}\" has the same name as a command, ignoring it");
+        return args;
+    }
+
+    let mut expanded = args;
+    expanded.splice(pos..=pos, expansion.split_whitespace().map(OsString::from));
+    expanded
+}
+
+/// Position of the command in the arguments, skipping global options (and their values)
+fn command_position(args: &[OsString]) -> Option<usize> {
+    let cmd = Cli::command();
+    let takes_value = |arg: &str| {
+        cmd.get_arguments()
+            .filter(|a| a.get_action().takes_values())
+            .any(|a| match arg.strip_prefix("--") {
+                Some(long) => a.get_long() == Some(long),
+                None => a.get_short().is_some_and(|short| arg[1..] == short.to_string()),
+            })
+    };
+
+    let mut i = 1;
+
+    while i < args.len() {
+        let arg = args[i].to_str()?;
+
+        if !arg.starts_with('-') {
+            return Some(i);
+        }
+
+        if arg == "--" {
+            return None;
+        }
+
+        // value follows the option as a separate argument, unless given as `--option=value`
+        i += if takes_value(arg) { 2 } else { 1 };
+    }
+
+    None
+}
@@ -1,15 +1,21 @@
-use clap::Args;
 use eyre::eyre;
 use eyre::Context;
 use eyre::Result as EResult;
 use serde_json::{json, Map, Value};
-use std::fs::File;
-use std::io::{BufReader, BufWriter, Read};
-use std::path::PathBuf;
+use std::io::{self, Read};
 use tap::Pipe;
 
+use crate::error::{Error, Result};
 use crate::utils;
 
+#[cfg(feature = "cli")]
+use {
+    crate::{exit::Failure, i18n::tr, vfs},
+    clap::Args,
+    std::path::PathBuf,
+};
+
+#[cfg(feature = "cli")]
 #[derive(Args)]
 #[derive(Debug)]
 pub struct Ops {
@@ -31,6 +37,7 @@ pub struct Ops {
     output_path: Option<PathBuf>,
 }
 
+#[cfg(feature = "cli")]
 pub fn handler(ops: Ops) -> EResult<()> {
     log::info!("Converting old binary save file to new JSON format");
 
@@ -40,21 +47,13 @@ pub fn handler(ops: Ops) -> EResult<()> {
 
     log::info!("Reading input file {}", input_path.display());
 
-    let input_file = File::open(&input_path).context("Failed to open input file")?;
-    let mut reader = BufReader::new(input_file);
+    let input = vfs::current().read(&input_path).context("Failed to open input file")?;
 
     // ======== Convert
 
-    read4b(&mut reader).context("Failed to read the first 4 bytes... Somehow")?;
-
     log::info!("Converting binary data to JSON");
 
-    let data = read_value(&mut reader).context("Failed to read the main data of the save file")?;
-
-    let json = json!({
-        "version": 1,
-        utils::SAVE_DATA_KEY: data
-    });
+    let json = convert(&mut input.as_slice()).context("Failed to convert the save")?;
 
     // ======== Write output
 
@@ -75,16 +74,45 @@ pub fn handler(ops: Ops) -> EResult<()> {
         })
         .unwrap_or_else(|| utils::with_added_extension(&input_path, "json"));
 
+    if utils::file_exists(&output_path)
+        && !utils::confirm(&tr!("confirm-overwrite-file", path = output_path.display()))?
+    {
+        return Err(Failure::WriteRefused.error("Cancelled"));
+    }
+
     log::info!("Writing output to {}", output_path.display());
 
-    let output_file = File::create(&output_path).context("Failed to create output file")?;
-    serde_json::to_writer_pretty(BufWriter::new(output_file), &json).context("Failed to write output JSON to file")?;
+    utils::write_json_file(&output_path, &json).context("Failed to write output JSON to file")?;
 
     log::info!("Finished save conversion");
 
     Ok(())
 }
 
+/// Parse an old binary save (`savegame.bin` and the like), returning it in the JSON format the release uses
+///
+/// Values the format has no data for are dropped with a warning, as the game doesn't read them anyway
+///
+/// The save is read 4 bytes at a time, so `reader` should be a slice of it already in memory (like
+/// `&mut &bytes[..]`, which also works with a memory-mapped file) rather than an unbuffered file
+pub fn convert(reader: &mut impl Read) -> Result<Value> {
+    let mut reader = Tracked { inner: reader, offset: 0 };
+
+    read4b(&mut reader).context("Failed to read the first 4 bytes... Somehow")?;
+
+    let data = read_value(&mut reader, 0).context("Failed to read the main data of the save file")?;
+
+    Ok(json!({
+        "version": 1,
+        utils::SAVE_DATA_KEY: data
+    }))
+}
+
+/// Objects and arrays nested deeper than this are rejected, as no save has a reason for that many levels
+const MAX_DEPTH: usize = 64;
+/// Most elements to allocate for up front, as the length may be garbage in a broken file
+const PREALLOCATE_MAX: usize = 1024;
+
 #[derive(Debug, PartialEq)]
 enum Type {
     Bool,
@@ -98,7 +126,7 @@ enum Type {
 }
 
 impl Type {
-    fn from_marker(marker: [u8; 4]) -> EResult<Type> {
+    fn from_marker(marker: [u8; 4], offset: u64) -> EResult<Type> {
         if marker[1..] != [0, 0, 0] {
             return Err(eyre!("Unexpected marker structure: {marker:02X?}"));
         }
@@ -112,18 +140,19 @@ impl Type {
             0x12 => Ok(Type::Reference),
             0x14 => Ok(Type::Object),
             0x15 => Ok(Type::Array),
-            val => Err(eyre!("Unexpected marker value: {val:02X}")),
+            byte => Err(Error::UnsupportedMarker { byte, offset }.into()),
         }
     }
 
-    fn read_marker(reader: &mut BufReader<File>) -> EResult<Type> {
-        read4b(reader)
-            .context("Failed to read marker bytes")?
-            .pipe(Self::from_marker)
+    fn read_marker(reader: &mut Tracked<impl Read>) -> EResult<Type> {
+        let offset = reader.offset;
+        let marker = read4b(reader).context("Failed to read marker bytes")?;
+
+        Self::from_marker(marker, offset)
     }
 }
 
-fn read4b(reader: &mut BufReader<File>) -> EResult<[u8; 4]> {
+fn read4b(reader: &mut impl Read) -> EResult<[u8; 4]> {
     let mut buf4b: [u8; 4] = [0; 4];
 
     reader
@@ -133,7 +162,7 @@ fn read4b(reader: &mut BufReader<File>) -> EResult<[u8; 4]> {
     Ok(buf4b)
 }
 
-fn read_len(reader: &mut BufReader<File>, ty: Type) -> EResult<u32> {
+fn read_len(reader: &mut impl Read, ty: Type) -> EResult<u32> {
     match ty {
         Type::String => read4b(reader)
             .context("Failed to read data length bytes")?
@@ -156,7 +185,7 @@ fn read_len(reader: &mut BufReader<File>, ty: Type) -> EResult<u32> {
     }
 }
 
-fn read_string(reader: &mut BufReader<File>, check_marker: bool) -> EResult<String> {
+fn read_string(reader: &mut Tracked<impl Read>, check_marker: bool) -> EResult<String> {
     if check_marker {
         let ty = Type::read_marker(reader)?;
 
@@ -167,10 +196,16 @@ fn read_string(reader: &mut BufReader<File>, check_marker: bool) -> EResult<Stri
 
     let str_len = read_len(reader, Type::String)?;
 
-    let mut str_bytes = vec![0; str_len as usize];
+    // length comes from the file, so the bytes have to actually be there before it's trusted with allocating
+    let mut str_bytes = Vec::new();
     reader
-        .read_exact(&mut str_bytes)
+        .take(str_len as u64)
+        .read_to_end(&mut str_bytes)
         .context("Failed to read string bytes")?;
+
+    if str_bytes.len() != str_len as usize {
+        return Err(eyre!("String is {str_len} bytes long, but file ends after {}", str_bytes.len()));
+    }
     let str = String::from_utf8(str_bytes).context("Read string was not valid UTF-8")?;
 
     // Strings are padded to align with 4 bytes
@@ -185,16 +220,20 @@ fn read_string(reader: &mut BufReader<File>, check_marker: bool) -> EResult<Stri
     Ok(str)
 }
 
-fn read_f32(reader: &mut BufReader<File>) -> EResult<f32> {
+fn read_f32(reader: &mut impl Read) -> EResult<f32> {
     read4b(reader)
         .context("Failed to read f32 bytes")?
         .pipe(f32::from_le_bytes)
         .pipe(Ok)
 }
 
-fn read_value(reader: &mut BufReader<File>) -> EResult<Value> {
+fn read_value(reader: &mut Tracked<impl Read>, depth: usize) -> EResult<Value> {
     let ty = Type::read_marker(reader).context("Failed to read type of the value")?;
 
+    if matches!(ty, Type::Object | Type::Array) && depth >= MAX_DEPTH {
+        return Err(eyre!("Values are nested deeper than {MAX_DEPTH} levels"));
+    }
+
     match ty {
         Type::Bool => {
             let bytes = read4b(reader).context("Failed to read Bool bytes")?;
@@ -236,12 +275,12 @@ fn read_value(reader: &mut BufReader<File>) -> EResult<Value> {
         }
         Type::Object => {
             let len = read_len(reader, Type::Object).context("Failed to read field amount for object")?;
-            let mut fields = Map::with_capacity(len as usize);
+            let mut fields = Map::with_capacity((len as usize).min(PREALLOCATE_MAX));
 
             for i in 0..len {
                 let name = read_string(reader, true).with_context(|| format!("Failed to read {i}th field's name"))?;
-                let value =
-                    read_value(reader).with_context(|| format!("Failed to read value of '{name}' ({i}th field)"))?;
+                let value = read_value(reader, depth + 1)
+                    .with_context(|| format!("Failed to read value of '{name}' ({i}th field)"))?;
 
                 if value.is_null() {
                     log::warn!("Got NULL value for {name} ({i}th field) - skipping");
@@ -256,10 +295,10 @@ fn read_value(reader: &mut BufReader<File>) -> EResult<Value> {
         }
         Type::Array => {
             let len = read_len(reader, Type::Object).context("Failed to read field amount for object")?;
-            let mut values: Vec<Value> = Vec::with_capacity(len as usize);
+            let mut values: Vec<Value> = Vec::with_capacity((len as usize).min(PREALLOCATE_MAX));
 
             for i in 0..len {
-                let value = read_value(reader).with_context(|| format!("Failed to read {i}th element"))?;
+                let value = read_value(reader, depth + 1).with_context(|| format!("Failed to read {i}th element"))?;
 
                 if value.is_null() {
                     log::warn!("Got NULL value for {i}th element - skipping");
@@ -272,3 +311,18 @@ fn read_value(reader: &mut BufReader<File>) -> EResult<Value> {
         }
     }
 }
+
+/// Reader that keeps track of how far into the file it got, to point at the unexpected values
+struct Tracked<R> {
+    inner: R,
+    offset: u64,
+}
+
+impl<R: Read> Read for Tracked<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.offset += read as u64;
+
+        Ok(read)
+    }
+}
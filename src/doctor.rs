@@ -0,0 +1,207 @@
+use clap::Args;
+use eyre::{Report, Result as EResult};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::process;
+
+use crate::config::Config;
+use crate::exit::{self, Failure};
+use crate::i18n::tr;
+use crate::outfits::{self, OUTFITS_FILE};
+use crate::output;
+use crate::save::SaveFile;
+use crate::utils::{self, ObjExt, SaveDirHandler};
+
+#[derive(Args)]
+#[derive(Debug)]
+pub struct Ops {}
+
+pub fn handler(_ops: Ops, mut save_dir: SaveDirHandler) -> EResult<()> {
+    log::info!("Checking the environment");
+
+    let mut findings: Vec<Finding> = Vec::new();
+
+    check_config(&mut findings);
+    check_game(&mut findings);
+
+    if check_save_dir(&mut findings, &mut save_dir) {
+        for slot in 0..=3 {
+            check_slot(&mut findings, &mut save_dir, slot);
+        }
+
+        check_file(&mut findings, "Outfits", save_dir.resolve_file(OUTFITS_FILE), outfits::outfit_names);
+    }
+
+    output::print(&findings, || {
+        for Finding { check, status, message, hint } in &findings {
+            println!("{:<9} {check}: {message}", format!("[{}]", tr!(&format!("status-{status}"))));
+
+            if let Some(hint) = hint {
+                println!("{:<9} {hint}", "");
+            }
+        }
+    })?;
+
+    match findings.iter().filter(|f| f.status == "problem").count() {
+        0 => Ok(()),
+        problems => Err(Failure::Invalid.error(format!("Found {problems} problems"))),
+    }
+}
+
+fn check_config(findings: &mut Vec<Finding>) {
+    let Some(path) = Config::path() else {
+        findings.push(Finding::warning("Config", "Unable to determine system's config dir", None::<&str>));
+        return;
+    };
+
+    if !utils::file_exists(&path) {
+        findings.push(Finding::ok("Config", format!("{} doesn't exist, using defaults", path.display())));
+        return;
+    }
+
+    match Config::load() {
+        Ok(_) => findings.push(Finding::ok("Config", path.display())),
+        Err(err) => findings.push(Finding::problem("Config", format!("{err:#}"), Some("Fix it with `open config`"))),
+    }
+}
+
+fn check_game(findings: &mut Vec<Finding>) {
+    match game_running() {
+        Some(true) => findings.push(Finding::warning(
+            "Game",
+            "Running, it may overwrite any changes made to the saves",
+            Some("Close the game before changing its saves"),
+        )),
+        Some(false) => findings.push(Finding::ok("Game", "Not running")),
+        None => log::debug!("Unable to check whether the game is running"),
+    }
+}
+
+/// Whether the game is running, `None` if that can't be checked
+fn game_running() -> Option<bool> {
+    let output = if cfg!(target_os = "windows") {
+        process::Command::new("tasklist").output()
+    } else {
+        process::Command::new("ps").args(["-A", "-o", "comm="]).output()
+    };
+
+    let output = output.ok().filter(|o| o.status.success())?;
+
+    Some(String::from_utf8_lossy(&output.stdout).to_lowercase().contains("hardcoded"))
+}
+
+/// Returns whether the save dir was found, nothing else can be checked without it
+fn check_save_dir(findings: &mut Vec<Finding>, save_dir: &mut SaveDirHandler) -> bool {
+    let overridden = save_dir.is_overridden();
+
+    match save_dir.get_save_dir() {
+        Ok(dir) => {
+            let method = if overridden { "specified with --save-dir" } else { "found automatically" };
+            findings.push(Finding::ok("Save dir", format!("{} ({method})", dir.display())));
+
+            true
+        }
+        Err(err) => {
+            let err = Report::from(err);
+            findings.push(Finding::problem("Save dir", format!("{err:#}"), exit::suggestion(&err)));
+
+            false
+        }
+    }
+}
+
+fn check_slot(findings: &mut Vec<Finding>, save_dir: &mut SaveDirHandler, slot: u8) {
+    let name = format!("Slot {slot}");
+    let Ok(path) = save_dir.resolve_save_slot(slot) else {
+        return;
+    };
+    let backup = utils::with_added_extension(&path, "bak");
+
+    if !utils::file_exists(&path) {
+        findings.push(Finding::ok(&name, "Empty"));
+        return;
+    }
+
+    match SaveFile::open(save_dir, slot).and_then(|save| save.data().map(|_| ())) {
+        Ok(()) => findings.push(Finding::ok(&name, "Valid")),
+        Err(err) if utils::file_exists(&backup) => findings.push(Finding::problem(
+            &name,
+            format!("{err:#}"),
+            Some(format!("Original from before the last change is kept in {}", backup.display())),
+        )),
+        Err(err) => findings.push(Finding::problem(&name, format!("{err:#}"), None::<&str>)),
+    }
+
+    if !utils::file_exists(&backup) {
+        return;
+    }
+
+    let name = format!("Backup {slot}");
+    let valid = utils::read_json_file(&backup)
+        .and_then(|json| json.as_object().cloned().ok_or_else(|| Failure::Invalid.error("Not a JSON object")))
+        .and_then(|obj| obj.get_obj(utils::SAVE_DATA_KEY).map(|_| ()));
+
+    match valid {
+        Ok(()) => findings.push(Finding::ok(&name, "Valid")),
+        Err(err) => findings.push(Finding::warning(
+            &name,
+            format!("{err:#}"),
+            Some("It will be replaced the next time the save is changed"),
+        )),
+    }
+}
+
+fn check_file(
+    findings: &mut Vec<Finding>,
+    name: &str,
+    path: crate::Result<PathBuf>,
+    names: fn(&Path) -> crate::Result<Vec<String>>,
+) {
+    let Ok(path) = path else {
+        return;
+    };
+
+    if !utils::file_exists(&path) {
+        findings.push(Finding::ok(name, format!("{} wasn't created yet", path.display())));
+        return;
+    }
+
+    match names(&path) {
+        Ok(names) => findings.push(Finding::ok(name, format!("{} saved in {}", names.len(), path.display()))),
+        Err(err) => findings.push(Finding::problem(
+            name,
+            format!("{err:#}"),
+            Some(format!("Fix or remove {}", path.display())),
+        )),
+    }
+}
+
+#[derive(Serialize)]
+#[derive(Debug)]
+struct Finding {
+    check: String,
+    /// `ok`, `warning` or `problem`
+    status: &'static str,
+    message: String,
+    /// What can be done about it
+    hint: Option<String>,
+}
+
+impl Finding {
+    fn ok(check: &str, message: impl ToString) -> Self {
+        Self { check: check.to_string(), status: "ok", message: message.to_string(), hint: None }
+    }
+
+    fn warning(check: &str, message: impl ToString, hint: Option<impl ToString>) -> Self {
+        Self { status: "warning", ..Self::problem(check, message, hint) }
+    }
+
+    fn problem(check: &str, message: impl ToString, hint: Option<impl ToString>) -> Self {
+        Self {
+            check: check.to_string(),
+            status: "problem",
+            message: message.to_string(),
+            hint: hint.map(|h| h.to_string()),
+        }
+    }
+}
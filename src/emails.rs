@@ -0,0 +1,196 @@
+use clap::{Args, Subcommand};
+use eyre::{Context, Result as EResult};
+use serde::Serialize;
+
+use crate::exit::Failure;
+use crate::i18n::tr;
+use crate::output;
+use crate::save::SaveFile;
+use crate::utils::{self, SaveDirHandler};
+
+#[derive(Args)]
+#[derive(Debug)]
+pub struct Ops {
+    #[command(subcommand)]
+    action: Cmd,
+}
+
+#[derive(Subcommand)]
+#[derive(Debug)]
+enum Cmd {
+    /// List emails in the save along with their read status
+    List {
+        /// Save slot number (0-3)
+        save_slot: u8,
+    },
+    /// Mark emails as read
+    MarkRead {
+        /// Save slot number (0-3)
+        save_slot: u8,
+        /// IDs of the emails
+        #[arg(required = true)]
+        ids: Vec<i64>,
+    },
+    /// Mark emails as unread
+    MarkUnread {
+        /// Save slot number (0-3)
+        save_slot: u8,
+        /// IDs of the emails
+        #[arg(required = true)]
+        ids: Vec<i64>,
+    },
+    /// Delete emails from the save
+    ///
+    /// Every copy of the email is removed, whether read or not
+    Delete {
+        /// Save slot number (0-3)
+        save_slot: u8,
+        /// IDs of the emails
+        #[arg(required = true)]
+        ids: Vec<i64>,
+    },
+}
+
+pub fn handler(ops: Ops, mut save_dir: SaveDirHandler) -> EResult<()> {
+    log::info!("Working with emails");
+
+    match ops.action {
+        Cmd::List { save_slot } => list_emails(&mut save_dir, save_slot).context("Failed to list emails")?,
+        Cmd::MarkRead { save_slot, ids } => {
+            move_emails(&mut save_dir, save_slot, &ids, true).context("Failed to mark emails as read")?
+        }
+        Cmd::MarkUnread { save_slot, ids } => {
+            move_emails(&mut save_dir, save_slot, &ids, false).context("Failed to mark emails as unread")?
+        }
+        Cmd::Delete { save_slot, ids } => {
+            delete_emails(&mut save_dir, save_slot, &ids).context("Failed to delete emails")?
+        }
+    }
+
+    Ok(())
+}
+
+fn list_emails(save_dir: &mut SaveDirHandler, save_slot: u8) -> EResult<()> {
+    let save = SaveFile::open(save_dir, save_slot)?;
+    let save_data = save.typed_data()?;
+
+    // emails are stored in the same way they are shown in-game: newer first
+    let rows: Vec<EmailRow> = save_data
+        .unread_emails
+        .iter()
+        .map(|id| EmailRow { id: *id, status: "unread" })
+        .chain(save_data.read_emails.iter().map(|id| EmailRow { id: *id, status: "read" }))
+        .collect();
+
+    output::print(&rows, || {
+        for EmailRow { id, status } in &rows {
+            let status = tr!(&format!("status-{status}"));
+
+            println!("{id}\t{status}");
+        }
+    })
+}
+
+fn move_emails(save_dir: &mut SaveDirHandler, save_slot: u8, ids: &[i64], mark_read: bool) -> EResult<()> {
+    // ======== Read input
+
+    let mut save = SaveFile::open(save_dir, save_slot)?;
+    let mut save_data = save.typed_data()?;
+
+    let (from_ids, to_ids, to) = if mark_read {
+        (&mut save_data.unread_emails, &mut save_data.read_emails, "read")
+    } else {
+        (&mut save_data.read_emails, &mut save_data.unread_emails, "unread")
+    };
+
+    // ======== Moving
+
+    let mut moved = Vec::with_capacity(ids.len());
+
+    for &id in ids {
+        if from_ids.contains(&id) {
+            from_ids.retain(|e| *e != id);
+            moved.push(id);
+        } else if to_ids.contains(&id) {
+            log::info!("Email {id} is already {to}, skipping");
+        } else {
+            return Err(Failure::NotFound.error(format!("Email {id} not found in the save")));
+        }
+    }
+
+    if moved.is_empty() {
+        log::info!("Nothing to change");
+        return Ok(());
+    }
+
+    // keep the newer-first order of the given emails when putting them on top
+    for &id in moved.iter().rev() {
+        if !to_ids.contains(&id) {
+            to_ids.insert(0, id);
+        }
+    }
+
+    log::info!("Marked {} emails as {to}", moved.len());
+
+    // ======== Write output
+
+    save.set_typed_data(save_data)?;
+    save.write()?;
+
+    log::info!("Saved changes");
+
+    Ok(())
+}
+
+fn delete_emails(save_dir: &mut SaveDirHandler, save_slot: u8, ids: &[i64]) -> EResult<()> {
+    // ======== Read input
+
+    let mut save = SaveFile::open(save_dir, save_slot)?;
+    let mut save_data = save.typed_data()?;
+
+    // ======== Deleting
+
+    let mut removed = 0;
+    let mut found = Vec::with_capacity(ids.len());
+
+    for (label, list) in [("Read", &mut save_data.read_emails), ("Unread", &mut save_data.unread_emails)] {
+        found.extend(list.iter().filter(|id| ids.contains(id)).copied());
+        let len = list.len();
+
+        list.retain(|id| !ids.contains(id));
+
+        if list.len() != len {
+            log::info!("{label}: removing {} emails", len - list.len());
+            removed += len - list.len();
+        }
+    }
+
+    for id in ids.iter().filter(|id| !found.contains(*id)) {
+        log::warn!("Email {id} not found in the save, skipping");
+    }
+
+    if removed == 0 {
+        log::info!("None of the emails were found in the save, nothing to delete");
+        return Ok(());
+    }
+
+    if !utils::confirm(&tr!("confirm-delete-emails", count = removed, slot = save_slot))? {
+        return Err(Failure::WriteRefused.error("Cancelled"));
+    }
+
+    // ======== Write output
+
+    save.set_typed_data(save_data)?;
+    save.write()?;
+
+    log::info!("Removed {removed} emails");
+
+    Ok(())
+}
+
+#[derive(Serialize)]
+#[derive(Debug)]
+struct EmailRow {
+    id: i64,
+    status: &'static str,
+}
@@ -0,0 +1,128 @@
+//! Errors of the public API, for telling the failures apart without parsing their messages
+
+use eyre::Report;
+use std::error::Error as StdError;
+use std::fmt::{self, Display};
+use std::io;
+use std::path::PathBuf;
+
+use crate::exit::{Failure, FailureError};
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// Save slot or the save directory doesn't exist
+    SaveNotFound(Report),
+    /// Item, outfit, file, etc. being referred to doesn't exist
+    NotFound(Report),
+    /// Value was rejected (out of range, wrong type, etc.)
+    Invalid(Report),
+    /// Changes were not written, because they were declined or the save is in a state that doesn't allow them
+    WriteRefused(Report),
+    /// Save file isn't what the game writes
+    InvalidSave { path: PathBuf, reason: String },
+    /// Old binary save has a value of a type the converter doesn't know about
+    UnsupportedMarker {
+        byte: u8,
+        /// Position of the marker from the start of the file, in bytes
+        offset: u64,
+    },
+    /// Reading or writing a file failed
+    Io {
+        /// What was being done
+        context: String,
+        source: io::Error,
+    },
+    /// Anything else, with the whole chain of what was being done
+    Other(Report),
+}
+
+impl Error {
+    /// Details of the failure (like the suggestion on what to do about it), for the errors of [`Failure`] kinds
+    pub fn failure(&self) -> Option<&FailureError> {
+        self.report()?.chain().find_map(|err| err.downcast_ref::<FailureError>())
+    }
+
+    /// Kind of the failure, which decides the exit code of the CLI
+    pub fn kind(&self) -> Option<Failure> {
+        match self {
+            Error::InvalidSave { .. } => Some(Failure::Invalid),
+            _ => self.failure().map(FailureError::kind),
+        }
+    }
+
+    fn report(&self) -> Option<&Report> {
+        match self {
+            Error::SaveNotFound(report)
+            | Error::NotFound(report)
+            | Error::Invalid(report)
+            | Error::WriteRefused(report)
+            | Error::Other(report) => Some(report),
+            _ => None,
+        }
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(report) = self.report() {
+            return report.fmt(f);
+        }
+
+        match self {
+            Error::InvalidSave { path, reason } => write!(f, "Invalid save file {}: {reason}", path.display()),
+            Error::UnsupportedMarker { byte, offset } => {
+                write!(f, "Unsupported value type marker {byte:02X} at offset {offset}")
+            }
+            Error::Io { context, .. } => f.write_str(context),
+            _ => unreachable!("Errors with a report are displayed as it"),
+        }
+    }
+}
+
+impl StdError for Error {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match self {
+            Error::Io { source, .. } => Some(source),
+            _ => self.report()?.chain().nth(1),
+        }
+    }
+}
+
+impl From<FailureError> for Error {
+    fn from(failure: FailureError) -> Self {
+        Report::new(failure).into()
+    }
+}
+
+/// Finds out what kind of error it is from the errors in its chain
+///
+/// Context the report got after an [`Error`] was made into it is lost, as is the context of an [`io::Error`]
+/// besides the last one
+impl From<Report> for Error {
+    fn from(report: Report) -> Self {
+        let failure = report.chain().find_map(|err| err.downcast_ref::<FailureError>()).map(FailureError::kind);
+
+        match failure {
+            Some(Failure::SaveNotFound) => return Error::SaveNotFound(report),
+            Some(Failure::NotFound) => return Error::NotFound(report),
+            Some(Failure::Invalid) => return Error::Invalid(report),
+            Some(Failure::WriteRefused) => return Error::WriteRefused(report),
+            None => {}
+        }
+
+        let context = report.to_string();
+
+        let report = match report.downcast::<Error>() {
+            Ok(err) => return err,
+            Err(report) => report,
+        };
+
+        match report.downcast::<io::Error>() {
+            Ok(source) => Error::Io { context, source },
+            Err(report) => Error::Other(report),
+        }
+    }
+}
@@ -0,0 +1,150 @@
+#[cfg(feature = "cli")]
+use clap::ValueEnum;
+use eyre::Report;
+use serde_json::{json, Value};
+use std::error::Error as StdError;
+use std::fmt::{self, Display};
+use std::process::ExitCode;
+
+use crate::error::Error;
+use crate::i18n::tr;
+
+/// Kind of failure, reported to the caller through the exit code
+///
+/// Errors that don't have a kind attached exit with `1`, and invalid command line usage (reported by clap) with `2`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Failure {
+    /// Save slot or the save directory doesn't exist
+    SaveNotFound = 3,
+    /// Value given by the user, or the save file, was rejected
+    Invalid = 4,
+    /// Item, outfit, email, etc. being referred to doesn't exist
+    NotFound = 5,
+    /// Changes were not written, because the user declined them or the save is in a state that doesn't allow them
+    WriteRefused = 6,
+}
+
+impl Failure {
+    /// Make an error of this kind
+    pub fn error(self, message: impl Display) -> Report {
+        self.details(message).into()
+    }
+
+    /// Make an error of this kind, to attach more details to
+    pub fn details(self, message: impl Display) -> FailureError {
+        FailureError { kind: self, message: message.to_string(), key: None, suggestion: None }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Failure::SaveNotFound => "save_not_found",
+            Failure::Invalid => "invalid",
+            Failure::NotFound => "not_found",
+            Failure::WriteRefused => "write_refused",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct FailureError {
+    kind: Failure,
+    message: String,
+    /// File path or save key the error is about
+    key: Option<String>,
+    /// What the user could do about the error
+    suggestion: Option<String>,
+}
+
+impl FailureError {
+    pub fn kind(&self) -> Failure {
+        self.kind
+    }
+
+    pub fn key(mut self, key: impl Display) -> Self {
+        self.key = Some(key.to_string());
+        self
+    }
+
+    pub fn suggestion(mut self, suggestion: impl Display) -> Self {
+        self.suggestion = Some(suggestion.to_string());
+        self
+    }
+}
+
+impl Display for FailureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl StdError for FailureError {}
+
+/// Format in which errors are printed to stderr
+#[cfg_attr(feature = "cli", derive(ValueEnum))]
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub enum ErrorFormat {
+    /// Human-readable report
+    #[default]
+    Text,
+    /// JSON object, for frontends wrapping the Multitool
+    Json,
+}
+
+/// Failure details anywhere in the chain of the error
+fn failure(report: &Report) -> Option<&FailureError> {
+    report.chain().find_map(|err| {
+        err.downcast_ref::<FailureError>()
+            .or_else(|| err.downcast_ref::<Error>().and_then(Error::failure))
+    })
+}
+
+/// Suggestion on how to fix the failure, if it has one
+pub fn suggestion(report: &Report) -> Option<&str> {
+    failure(report).and_then(|f| f.suggestion.as_deref())
+}
+
+/// Kind of the failure, if it has one
+pub fn kind(report: &Report) -> Option<Failure> {
+    report.chain().find_map(|err| {
+        err.downcast_ref::<FailureError>()
+            .map(FailureError::kind)
+            .or_else(|| err.downcast_ref::<Error>().and_then(Error::kind))
+    })
+}
+
+pub fn code(report: &Report) -> ExitCode {
+    match kind(report) {
+        Some(kind) => ExitCode::from(kind as u8),
+        None => ExitCode::FAILURE,
+    }
+}
+
+/// Print the error to stderr in the specified format
+pub fn print(report: &Report, format: ErrorFormat) {
+    match format {
+        ErrorFormat::Text => {
+            eprintln!("{}: {report:?}", tr!("error"));
+
+            if let Some(suggestion) = suggestion(report) {
+                eprintln!("\n{}: {suggestion}", tr!("suggestion"));
+            }
+        }
+        ErrorFormat::Json => eprintln!("{}", to_json(report)),
+    }
+}
+
+/// Error as the JSON object printed with `--error-format json`
+pub fn to_json(report: &Report) -> Value {
+    let failure = failure(report);
+    let kind = kind(report);
+    let causes: Vec<String> = report.chain().skip(1).map(ToString::to_string).collect();
+
+    json!({
+        "kind": kind.map_or("other", Failure::name),
+        "code": kind.map_or(1, |kind| kind as u8),
+        "message": report.to_string(),
+        "causes": causes,
+        "key": failure.and_then(|f| f.key.as_deref()),
+        "suggestion": failure.and_then(|f| f.suggestion.as_deref()),
+    })
+}
@@ -0,0 +1,66 @@
+//! C interface of the converter, enabled with the `ffi` feature
+//!
+//! Declarations are in `include/hc_multitool.h`. Every string returned by these functions is owned by the library,
+//! and has to be released with [`hc_string_free`]
+
+use eyre::Context;
+use std::ffi::{c_char, CString};
+use std::panic;
+use std::ptr;
+
+use crate::converter;
+
+/// Convert an old binary save (contents of `savegame.bin` and the like) into the JSON the release uses
+///
+/// Returns the JSON as a NUL-terminated string, or null if it failed. On failure, if `error` isn't null, it's set to
+/// the error message. Panics don't cross into the caller, they fail the conversion instead
+///
+/// # Safety
+///
+/// `data` has to point to `len` readable bytes, and `error` has to be either null or valid for writing
+#[no_mangle]
+pub unsafe extern "C" fn hc_convert_bin_to_json(data: *const u8, len: usize, error: *mut *mut c_char) -> *mut c_char {
+    if !error.is_null() {
+        *error = ptr::null_mut();
+    }
+
+    let input = if data.is_null() { &[][..] } else { std::slice::from_raw_parts(data, len) };
+
+    // unwinding out of an `extern "C"` function aborts the whole process
+    let result = panic::catch_unwind(|| convert(input).map_err(|err| format!("{:#}", eyre::Report::from(err))))
+        .unwrap_or_else(|_| Err("Conversion failed unexpectedly".to_string()));
+
+    match result {
+        Ok(json) => into_c_string(json),
+        Err(message) => {
+            if !error.is_null() {
+                *error = into_c_string(message);
+            }
+
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Release a string returned by the library
+///
+/// # Safety
+///
+/// `s` has to be null, or a string returned by the library that wasn't released yet
+#[no_mangle]
+pub unsafe extern "C" fn hc_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+fn convert(mut input: &[u8]) -> crate::Result<String> {
+    let json = converter::convert(&mut input)?;
+
+    Ok(serde_json::to_string_pretty(&json).context("Failed to serialize JSON")?)
+}
+
+fn into_c_string(s: String) -> *mut c_char {
+    // neither JSON nor the error messages have NULs in them, but just in case
+    CString::new(s.replace('\0', "")).unwrap_or_default().into_raw()
+}
@@ -0,0 +1,199 @@
+use clap::{Args, Subcommand};
+use eyre::{Context, Result as EResult};
+use serde::Serialize;
+use std::str::FromStr;
+
+use crate::exit::Failure;
+use crate::i18n::tr;
+use crate::known;
+use crate::output;
+use crate::save::{FurnitureItem, SaveFile};
+use crate::utils::{self, SaveDirHandler};
+
+#[derive(Args)]
+#[derive(Debug)]
+pub struct Ops {
+    #[command(subcommand)]
+    action: Cmd,
+}
+
+#[derive(Subcommand)]
+#[derive(Debug)]
+enum Cmd {
+    /// List furniture items along with their position (`#`) in the list
+    List {
+        /// Save slot number (0-3)
+        save_slot: u8,
+        /// Only list items with names containing this
+        #[arg(short, long)]
+        name: Option<String>,
+    },
+    /// Add a furniture item, putting it into storage
+    Add {
+        /// Save slot number (0-3)
+        save_slot: u8,
+        /// Name of the item
+        name: String,
+    },
+    /// Grant furniture items, putting them into storage
+    ///
+    /// Same as `add`, but takes several items at once
+    Grant {
+        /// Save slot number (0-3)
+        save_slot: u8,
+        /// Names of the items
+        #[arg(required = true)]
+        names: Vec<String>,
+    },
+    /// Remove a furniture item
+    Remove {
+        /// Save slot number (0-3)
+        save_slot: u8,
+        /// Name of the item, or its position (`#`) in the list
+        item: ItemRef,
+    },
+}
+
+pub fn handler(ops: Ops, mut save_dir: SaveDirHandler) -> EResult<()> {
+    log::info!("Working with furniture");
+
+    match ops.action {
+        Cmd::List { save_slot, name } => {
+            list_furniture(&mut save_dir, save_slot, name.as_deref()).context("Failed to list furniture")?
+        }
+        Cmd::Add { save_slot, name } => {
+            grant_furniture(&mut save_dir, save_slot, &[name]).context("Failed to add furniture")?
+        }
+        Cmd::Grant { save_slot, names } => {
+            grant_furniture(&mut save_dir, save_slot, &names).context("Failed to grant furniture")?
+        }
+        Cmd::Remove { save_slot, item } => {
+            remove_furniture(&mut save_dir, save_slot, &item).context("Failed to remove furniture")?
+        }
+    }
+
+    Ok(())
+}
+
+fn list_furniture(save_dir: &mut SaveDirHandler, save_slot: u8, filter: Option<&str>) -> EResult<()> {
+    let save = SaveFile::open(save_dir, save_slot)?;
+    let save_data = save.typed_data()?;
+
+    let rows: Vec<FurnRow> = save_data
+        .furniture
+        .iter()
+        .enumerate()
+        .filter(|(_, item)| filter.is_none_or(|f| item.name.contains(f)))
+        .map(|(index, item)| FurnRow { index, name: &item.name })
+        .collect();
+
+    output::print(&rows, || {
+        println!("{:>5}  {}", tr!("header-index"), tr!("header-name"));
+
+        for FurnRow { index: i, name } in &rows {
+            println!("{i:>5}  {name}");
+        }
+    })
+}
+
+fn grant_furniture(save_dir: &mut SaveDirHandler, save_slot: u8, names: &[String]) -> EResult<()> {
+    let mut save = SaveFile::open(save_dir, save_slot)?;
+    let mut save_data = save.typed_data()?;
+
+    for name in names {
+        if !known::FURNITURE.iter().any(|(known, _)| known == name) {
+            log::warn!("{name}: not a known furniture item, granting anyway");
+        }
+
+        log::info!("Granting {name}");
+
+        save_data.furniture.push(FurnitureItem::new(name));
+    }
+
+    save.set_typed_data(save_data)?;
+    save.write()?;
+
+    log::info!("Granted {} items", names.len());
+
+    Ok(())
+}
+
+fn remove_furniture(save_dir: &mut SaveDirHandler, save_slot: u8, item: &ItemRef) -> EResult<()> {
+    let mut save = SaveFile::open(save_dir, save_slot)?;
+    let mut save_data = save.typed_data()?;
+
+    let index = item.resolve(&save_data.furniture)?;
+    let name = &save_data.furniture[index].name;
+
+    if !utils::confirm(&tr!("confirm-remove-furniture", index = index, name = name, slot = save_slot))? {
+        return Err(Failure::WriteRefused.error("Cancelled"));
+    }
+
+    let removed = save_data.furniture.remove(index);
+
+    log::info!("Removed #{index}: {}", removed.name);
+
+    save.set_typed_data(save_data)?;
+    save.write()?;
+
+    log::info!("Saved changes");
+
+    Ok(())
+}
+
+/// Reference to an item in the furniture list
+#[derive(Clone)]
+#[derive(Debug)]
+enum ItemRef {
+    Index(usize),
+    Name(String),
+}
+
+impl ItemRef {
+    fn resolve(&self, items: &[FurnitureItem]) -> EResult<usize> {
+        match self {
+            ItemRef::Index(i) if *i < items.len() => Ok(*i),
+            ItemRef::Index(i) => Err(Failure::NotFound
+                .details(format!("No item #{i}, list only has {} items", items.len()))
+                .suggestion("Use `furniture list` to see the items along with their #")
+                .into()),
+            ItemRef::Name(name) => {
+                let found: Vec<usize> = items
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, item)| &item.name == name)
+                    .map(|(i, _)| i)
+                    .collect();
+
+                match found[..] {
+                    [] => Err(Failure::NotFound
+                        .details(format!("No item named \"{name}\""))
+                        .suggestion("Use `furniture list` to see the items along with their #")
+                        .into()),
+                    [i] => Ok(i),
+                    _ => Err(Failure::Invalid.error(format!(
+                        "Multiple items named \"{name}\" ({found:?}), specify one by its # instead"
+                    ))),
+                }
+            }
+        }
+    }
+}
+
+impl FromStr for ItemRef {
+    type Err = std::convert::Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s.parse() {
+            Ok(i) => ItemRef::Index(i),
+            Err(_) => ItemRef::Name(s.to_string()),
+        })
+    }
+}
+
+#[derive(Serialize)]
+#[derive(Debug)]
+struct FurnRow<'a> {
+    index: usize,
+    name: &'a str,
+}
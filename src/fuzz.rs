@@ -0,0 +1,172 @@
+//! Random saves with the right structure, for fuzzing and tests. Enabled with the `fuzz` feature
+//!
+//! Same seed always makes the same save, so anything a fuzzer finds can be reproduced with just the seed
+
+use serde_json::{json, Value};
+
+use crate::save::{FurnitureItem, SaveData, Wardrobe};
+use crate::utils::{self, JObj};
+
+/// How deep the generated objects and arrays go
+const MAX_DEPTH: usize = 4;
+
+/// Generator of the saves
+#[derive(Clone)]
+#[derive(Debug)]
+pub struct SaveGen {
+    state: u64,
+}
+
+impl SaveGen {
+    pub fn new(seed: u64) -> Self {
+        // xorshift gets stuck on 0
+        Self { state: seed ^ 0x9E37_79B9_7F4A_7C15 }
+    }
+
+    /// Generator seeded with the first 8 bytes of the fuzzer's input
+    pub fn from_bytes(data: &[u8]) -> Self {
+        let mut seed = [0; 8];
+        let len = data.len().min(8);
+        seed[..len].copy_from_slice(&data[..len]);
+
+        Self::new(u64::from_le_bytes(seed))
+    }
+
+    /// Old binary save, with an object of random fields in it
+    pub fn binary_save(&mut self) -> Vec<u8> {
+        let mut out = vec![0; 4];
+        self.write_object(&mut out, 0);
+
+        out
+    }
+
+    /// JSON save, with all the lists the Multitool works with and random values in them
+    pub fn json_save(&mut self) -> Value {
+        let [hair, face, accessory, shirt, jacket] = [(); 5].map(|_| self.list(|gen| gen.word()));
+        // the game always has something worn, and only ever from the owned items
+        let worn = |list: &[String]| list.first().cloned().unwrap_or_default();
+
+        let furniture = self.list(|gen| FurnitureItem::new(gen.word()));
+
+        let mut other = JObj::new();
+        other.insert("money".to_string(), json!(self.float().abs()));
+        other.insert("day".to_string(), json!(self.below(100)));
+
+        let wardrobe = Wardrobe {
+            hair_worn: worn(&hair),
+            face_worn: worn(&face),
+            accessory_worn: worn(&accessory),
+            shirt_worn: worn(&shirt),
+            jacket_worn: worn(&jacket),
+            hair_owned: hair,
+            face_owned: face,
+            accessory_owned: accessory,
+            shirt_owned: shirt,
+            jacket_owned: jacket,
+        };
+
+        let data = SaveData {
+            wardrobe,
+            furniture,
+            read_emails: self.list(|gen| gen.below(50) as i64),
+            unread_emails: self.list(|gen| gen.below(50) as i64),
+            other,
+        };
+
+        json!({
+            "version": 1,
+            utils::SAVE_DATA_KEY: data,
+        })
+    }
+
+    fn write_value(&mut self, out: &mut Vec<u8>, depth: usize) {
+        let kinds = if depth < MAX_DEPTH { 6 } else { 4 };
+
+        match self.below(kinds) {
+            0 => {
+                out.extend([0x01, 0, 0, 0]);
+                out.extend((self.below(2) as u32).to_le_bytes());
+            }
+            1 => {
+                out.extend([0x02, 0, 0, 0]);
+                out.extend((self.next() as u32).to_le_bytes());
+            }
+            2 => {
+                let word = self.word();
+                write_string(out, &word);
+            }
+            3 => {
+                out.extend([0x05, 0, 0, 0]);
+                out.extend((self.float() as f32).to_le_bytes());
+                out.extend((self.float() as f32).to_le_bytes());
+            }
+            4 => self.write_object(out, depth),
+            _ => {
+                let len = self.below(8) as u32;
+
+                out.extend([0x15, 0, 0, 0]);
+                out.extend((len | 0x8000_0000).to_le_bytes());
+
+                for _ in 0..len {
+                    self.write_value(out, depth + 1);
+                }
+            }
+        }
+    }
+
+    fn write_object(&mut self, out: &mut Vec<u8>, depth: usize) {
+        let len = self.below(8) as u32;
+
+        out.extend([0x14, 0, 0, 0]);
+        out.extend((len | 0x8000_0000).to_le_bytes());
+
+        for _ in 0..len {
+            let name = self.word();
+            write_string(out, &name);
+            self.write_value(out, depth + 1);
+        }
+    }
+
+    fn list<T>(&mut self, mut item: impl FnMut(&mut Self) -> T) -> Vec<T> {
+        (0..self.below(10)).map(|_| item(self)).collect()
+    }
+
+    fn word(&mut self) -> String {
+        (0..1 + self.below(12)).map(|_| (b'a' + self.below(26) as u8) as char).collect()
+    }
+
+    fn float(&mut self) -> f64 {
+        // whole numbers are common in the saves, and are the ones most likely to change their looks
+        if self.chance(3) {
+            self.below(2000) as f64 - 1000.0
+        } else {
+            (self.next() % 200_000) as f64 / 100.0 - 1000.0
+        }
+    }
+
+    /// One in `n`
+    fn chance(&mut self, n: u64) -> bool {
+        self.below(n) == 0
+    }
+
+    fn below(&mut self, n: u64) -> u64 {
+        self.next() % n
+    }
+
+    /// xorshift64*
+    fn next(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+
+        self.state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+}
+
+/// String value, padded to 4 bytes as the game does
+fn write_string(out: &mut Vec<u8>, s: &str) {
+    out.extend([0x04, 0, 0, 0]);
+    out.extend((s.len() as u32).to_le_bytes());
+    out.extend(s.as_bytes());
+    out.extend(std::iter::repeat_n(0, (4 - s.len() % 4) % 4));
+}
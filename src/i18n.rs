@@ -0,0 +1,119 @@
+//! Translations of the text printed for the user
+//!
+//! Messages are looked up by their ID in a catalog of the current language, falling back to English for the ones
+//! missing from it. Catalogs are JSON objects of `"id": "message"`, with `{name}` in messages being replaced with
+//! the named arguments.
+//!
+//! Built-in catalogs live in `data/i18n`. Translations can also be dropped into `i18n/<language>.json` next to the
+//! config file, which take priority over the built-in ones.
+//!
+//! Logs are not translated.
+
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::sync::OnceLock;
+
+#[cfg(feature = "cli")]
+use {
+    crate::config::Config,
+    crate::utils,
+    eyre::{eyre, Context, Result as EResult},
+    std::env,
+    std::path::PathBuf,
+};
+
+const BUILT_IN: &[(&str, &str)] = &[("en", include_str!("../data/i18n/en.json"))];
+
+static CATALOG: OnceLock<Catalog> = OnceLock::new();
+
+type Catalog = HashMap<String, String>;
+
+/// Load the catalog for the language, or the one from the environment (`LANG`) when it's not specified
+#[cfg(feature = "cli")]
+pub fn init(language: Option<&str>) {
+    let explicit = language.is_some();
+    let language = language.map(String::from).or_else(env_language).unwrap_or_else(|| "en".to_string());
+
+    log::debug!("Using language {language}");
+
+    let mut catalog = english();
+
+    if language != "en" {
+        match load(&language) {
+            Ok(translated) => catalog.extend(translated),
+            // not having a translation for the system's language is normal, no need to warn about it every time
+            Err(err) if !explicit => log::debug!("{err:#}, using English"),
+            Err(err) => log::warn!("{err:#}, using English"),
+        }
+    }
+
+    if CATALOG.set(catalog).is_err() {
+        log::warn!("Language was already set");
+    }
+}
+
+/// Translated message, with arguments substituted
+///
+/// Unknown IDs are returned as is
+pub fn message(id: &str, args: &[(&str, &dyn Display)]) -> String {
+    let catalog = CATALOG.get_or_init(english);
+    let mut message = catalog.get(id).cloned().unwrap_or_else(|| id.to_string());
+
+    for (name, value) in args {
+        message = message.replace(&format!("{{{name}}}"), &value.to_string());
+    }
+
+    message
+}
+
+/// Translate the message with the ID, e.g. `tr!("confirm-randomize", slot = save_slot)`
+macro_rules! tr {
+    ($id:expr) => {
+        $crate::i18n::message($id, &[])
+    };
+    ($id:expr, $($name:ident = $value:expr),+ $(,)?) => {
+        $crate::i18n::message($id, &[$((stringify!($name), &$value as &dyn std::fmt::Display)),+])
+    };
+}
+
+pub(crate) use tr;
+
+fn english() -> Catalog {
+    serde_json::from_str(BUILT_IN[0].1).expect("Built-in English catalog should be valid")
+}
+
+/// Language code from the locale, e.g. `de` for `de_DE.UTF-8`
+#[cfg(feature = "cli")]
+fn env_language() -> Option<String> {
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .into_iter()
+        .filter_map(|var| env::var(var).ok())
+        .find(|val| !val.is_empty())
+        .and_then(|val| val.split(['_', '.', '@']).next().map(str::to_lowercase))
+        .filter(|lang| lang != "c" && lang != "posix")
+}
+
+#[cfg(feature = "cli")]
+fn user_catalog_path(language: &str) -> Option<PathBuf> {
+    Config::path()?
+        .parent()
+        .map(|dir| dir.join(format!("i18n/{language}.json")))
+}
+
+#[cfg(feature = "cli")]
+fn load(language: &str) -> EResult<Catalog> {
+    if let Some(path) = user_catalog_path(language).filter(|p| utils::file_exists(p)) {
+        log::debug!("Loading translation from {}", path.display());
+
+        let json = utils::read_json_file(&path).context("Failed to read translation")?;
+
+        return serde_json::from_value(json).with_context(|| format!("Invalid translation {}", path.display()));
+    }
+
+    let (_, catalog) = BUILT_IN
+        .iter()
+        .find(|(lang, _)| *lang == language)
+        .ok_or_else(|| eyre!("No translation for language \"{language}\""))?;
+
+    serde_json::from_str(catalog).with_context(|| format!("Invalid built-in translation \"{language}\""))
+}
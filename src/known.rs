@@ -0,0 +1,31 @@
+//! Items known to exist in the game
+//!
+//! Maintained by hand from the game's data, so it may lag behind the latest release.
+//! Anything missing here is still handled fine when found in a save, it just can't be checked against.
+
+/// Whether [`COSMETICS`] and [`FURNITURE`] have every item of the game
+///
+/// Until they do, counts against them are only out of the items known so far
+pub const ITEMS_COMPLETE: bool = false;
+
+/// Wardrobe items, by the list they are stored in
+pub const COSMETICS: [(&str, &[&str]); 5] = [
+    ("hairlist", &["a", "k"]),
+    ("facelist", &["aa"]),
+    ("jewllist", &["a"]),
+    ("shirtlist", &["a", "z8"]),
+    ("jacketlist", &["a", "v3"]),
+];
+
+/// Furniture items along with their categories
+pub const FURNITURE: &[(&str, &str)] = &[("computer1", "electronics"), ("hc_journal", "decor")];
+
+/// Email IDs along with their subjects
+pub const EMAILS: &[(i64, &str)] = &[];
+
+pub fn cosmetics(list: &str) -> &'static [&'static str] {
+    COSMETICS
+        .iter()
+        .find(|(name, _)| *name == list)
+        .map_or(&[], |(_, items)| *items)
+}
@@ -0,0 +1,98 @@
+//! Tools for working with the saves of HARDCODED
+//!
+//! Besides the `hc_multitool` executable this crate can be used as a library, by GUI frontends and mod tools
+//! that want the same functionality without shelling out:
+//!
+//! - [`converter`] - parser for the old binary saves of the pre-release versions
+//! - [`save`] - reading and writing the JSON save slots
+//! - [`outfits`] - outfits storage, and taking off or putting on outfits in a save
+//! - [`organiser`] - sorting and deduplicating the lists inside a save
+//! - [`vfs`] - filesystem the files are read from and written to, which can be kept in memory instead
+//!
+//! Everything else is used by the executable, and isn't part of the public API.
+//!
+//! Functions of the public API fail with [`Error`], which tells apart the kinds of failures.
+//!
+//! ```no_run
+//! use hc_multitool::organiser;
+//! use hc_multitool::save::SaveFile;
+//! use hc_multitool::utils::SaveDirHandler;
+//!
+//! # fn main() -> hc_multitool::Result<()> {
+//! let mut save_dir = SaveDirHandler::new_override(None);
+//! let mut save = SaveFile::open(&mut save_dir, 0)?;
+//! let mut save_data = save.typed_data()?;
+//!
+//! organiser::organise(&mut save_data);
+//!
+//! save.set_typed_data(save_data)?;
+//! save.write()?;
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod converter;
+pub mod error;
+pub mod exit;
+pub mod organiser;
+pub mod outfits;
+pub mod save;
+pub mod utils;
+pub mod vfs;
+
+#[doc(hidden)]
+#[cfg(feature = "cli")]
+pub mod cli;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "fuzz")]
+pub mod fuzz;
+#[cfg(target_arch = "wasm32")]
+pub mod wasm;
+
+pub use error::{Error, Result};
+
+mod i18n;
+mod preserve;
+mod timings;
+
+#[cfg(feature = "cli")]
+mod cheat;
+#[cfg(feature = "cli")]
+mod completions;
+#[cfg(feature = "cli")]
+mod config;
+#[cfg(feature = "cli")]
+mod doctor;
+#[cfg(feature = "cli")]
+mod emails;
+#[cfg(feature = "cli")]
+mod furniture;
+#[cfg(feature = "cli")]
+mod known;
+#[cfg(feature = "cli")]
+mod log_file;
+#[cfg(feature = "cli")]
+mod ngplus;
+#[cfg(feature = "cli")]
+mod open;
+#[cfg(feature = "cli")]
+mod output;
+#[cfg(feature = "cli")]
+mod paths;
+#[cfg(feature = "cli")]
+mod progress;
+#[cfg(feature = "cli")]
+mod repl;
+#[cfg(feature = "cli")]
+mod rpc;
+#[cfg(feature = "cli")]
+mod run;
+#[cfg(feature = "cli")]
+mod self_update;
+#[cfg(feature = "cli")]
+mod serve;
+#[cfg(feature = "cli")]
+mod settings;
+#[cfg(feature = "cli")]
+mod wardrobe;
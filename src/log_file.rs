@@ -0,0 +1,82 @@
+//! Mirroring of the logs into a file, so they can be looked at after the fact
+//!
+//! File is rotated once it gets too big, keeping a few of the previous ones as `<file>.1`, `<file>.2` and so on
+
+use eyre::{Context, Result as EResult};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::utils;
+use crate::vfs::{self, Fs};
+
+/// Size after which the file is rotated
+const MAX_SIZE: u64 = 1024 * 1024;
+/// Amount of rotated files to keep
+const KEEP: u32 = 3;
+
+/// Writer that passes the logs to stderr, and a copy of them (without colors) to the file
+pub struct Mirror {
+    /// Filesystem at the time the file was opened, so that `--dry-run` (which swaps it later) still keeps the logs
+    fs: Arc<dyn Fs>,
+    path: PathBuf,
+}
+
+impl Mirror {
+    pub fn open(path: &Path) -> EResult<Self> {
+        let fs = vfs::current();
+
+        if fs.size(path).is_ok_and(|size| size > MAX_SIZE) {
+            rotate(fs.as_ref(), path).context("Failed to rotate log file")?;
+        }
+
+        fs.append(path, &[]).with_context(|| format!("Failed to open log file {}", path.display()))?;
+
+        Ok(Self { fs, path: path.to_owned() })
+    }
+}
+
+impl Write for Mirror {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        io::stderr().write_all(buf)?;
+        self.fs.append(&self.path, &strip_colors(buf))?;
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        io::stderr().flush()
+    }
+}
+
+fn rotate(fs: &dyn Fs, path: &Path) -> io::Result<()> {
+    let rotated = |n: u32| utils::with_added_extension(path, &n.to_string());
+
+    if fs.exists(&rotated(KEEP)) {
+        fs.remove(&rotated(KEEP))?;
+    }
+
+    for n in (1..KEEP).rev() {
+        if fs.exists(&rotated(n)) {
+            fs.rename(&rotated(n), &rotated(n + 1))?;
+        }
+    }
+
+    fs.rename(path, &rotated(1))
+}
+
+/// Remove terminal color codes (`ESC [ ... m`)
+fn strip_colors(buf: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(buf.len());
+    let mut bytes = buf.iter().copied();
+
+    while let Some(b) = bytes.next() {
+        if b == 0x1b {
+            bytes.by_ref().find(|b| b.is_ascii_alphabetic());
+        } else {
+            out.push(b);
+        }
+    }
+
+    out
+}
@@ -0,0 +1,95 @@
+use clap::{Args, ValueEnum};
+use eyre::Result as EResult;
+
+use crate::exit::Failure;
+use crate::save::{FurnitureItem, SaveFile, COSMETICS_LISTS};
+use crate::utils::SaveDirHandler;
+
+#[derive(Args)]
+#[derive(Debug)]
+pub struct Ops {
+    /// Save slot number (0-3) to carry things over from
+    save_slot: u8,
+    /// Save slot number (0-3) with a freshly started game to carry things into
+    #[arg(long)]
+    into: u8,
+    /// What to carry over
+    #[arg(long, value_delimiter = ',', default_values = ["cosmetics", "furniture"])]
+    carry: Vec<Category>,
+}
+
+#[derive(ValueEnum)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Category {
+    /// Owned wardrobe items
+    Cosmetics,
+    /// Owned furniture, which goes into storage
+    Furniture,
+}
+
+pub fn handler(ops: Ops, mut save_dir: SaveDirHandler) -> EResult<()> {
+    log::info!("Starting new game plus");
+
+    if ops.save_slot == ops.into {
+        return Err(Failure::Invalid.error("Can't carry things over into the same save"));
+    }
+
+    let source = SaveFile::open(&mut save_dir, ops.save_slot)?;
+    let source_data = source.typed_data()?;
+
+    let mut target = SaveFile::open(&mut save_dir, ops.into)?;
+    let mut target_data = target.typed_data()?;
+
+    if ops.carry.contains(&Category::Cosmetics) {
+        let lists = source_data.cosmetics().into_iter().zip(target_data.cosmetics_mut());
+
+        for ((_, label), (source, target)) in COSMETICS_LISTS.into_iter().zip(lists) {
+            let added = merge_list(source, target);
+
+            log::info!("{label}: carried over {added} items");
+        }
+    }
+
+    if ops.carry.contains(&Category::Furniture) {
+        let added = merge_furniture(&source_data.furniture, &mut target_data.furniture);
+
+        log::info!("Furniture: carried over {added} items");
+    }
+
+    target.set_typed_data(target_data)?;
+    target.write()?;
+
+    log::info!("Finished new game plus");
+
+    Ok(())
+}
+
+/// Add the values from source's list that target's doesn't have yet
+fn merge_list(source: &[String], target: &mut Vec<String>) -> usize {
+    let missing: Vec<String> = source.iter().filter(|item| !target.contains(item)).cloned().collect();
+
+    let added = missing.len();
+    target.extend(missing);
+
+    added
+}
+
+/// Put every item the source has more of than the target into target's storage
+fn merge_furniture(source: &[FurnitureItem], target: &mut Vec<FurnitureItem>) -> usize {
+    let mut owned: Vec<&str> = target.iter().map(|i| i.name.as_str()).collect();
+    let mut missing = Vec::new();
+
+    for item in source {
+        match owned.iter().position(|o| *o == item.name) {
+            Some(i) => {
+                owned.swap_remove(i);
+            }
+            None => missing.push(FurnitureItem::new(item.name.as_str())),
+        }
+    }
+
+    let added = missing.len();
+    target.extend(missing);
+
+    added
+}
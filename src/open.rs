@@ -0,0 +1,125 @@
+use clap::{Args, Subcommand};
+use eyre::{Context, ContextCompat, Result as EResult};
+use std::env;
+use std::path::Path;
+use std::process;
+
+use crate::config::Config;
+use crate::exit::Failure;
+use crate::outfits::OUTFITS_FILE;
+use crate::utils::{self, SaveDirHandler};
+use crate::vfs;
+
+#[derive(Args)]
+#[derive(Debug)]
+pub struct Ops {
+    #[command(subcommand)]
+    action: Cmd,
+}
+
+#[derive(Subcommand)]
+#[derive(Debug)]
+enum Cmd {
+    /// Open the save directory in the file manager
+    SaveDir,
+    /// Open the outfits file in the editor
+    Outfits,
+    /// Open the backup of a save slot in the editor, or the directory with all of them in the file manager
+    ///
+    /// Backups are kept next to the saves, as `savefile<slot>.json.bak`
+    Backups {
+        /// Save slot number (0-3)
+        save_slot: Option<u8>,
+    },
+    /// Open the config file in the editor
+    Config,
+}
+
+pub fn handler(ops: Ops, mut save_dir: SaveDirHandler) -> EResult<()> {
+    let path = match ops.action {
+        Cmd::SaveDir | Cmd::Backups { save_slot: None } => save_dir.get_save_dir()?.to_owned(),
+        Cmd::Outfits => save_dir.resolve_file(OUTFITS_FILE)?,
+        Cmd::Backups { save_slot: Some(slot) } => {
+            let path = utils::with_added_extension(&save_dir.resolve_save_slot(slot)?, "bak");
+
+            if !utils::file_exists(&path) {
+                return Err(Failure::NotFound
+                    .details(format!("Save slot {slot} has no backup"))
+                    .key(path.display())
+                    .suggestion("Backup is only made once the save is changed by the Multitool")
+                    .into());
+            }
+
+            path
+        }
+        Cmd::Config => {
+            let path = Config::path().context("Unable to determine system's config dir")?;
+
+            // so the editor can create the file if there isn't one yet
+            if let Some(dir) = path.parent() {
+                vfs::current().create_dir_all(dir).context("Failed to create config directory")?;
+            }
+
+            path
+        }
+    };
+
+    if vfs::current().is_dir(&path) {
+        open_in_file_manager(&path)
+    } else {
+        open_in_editor(&path)
+    }
+}
+
+/// Open the file in `$VISUAL` or `$EDITOR`, or whatever the system opens it with if neither is set
+fn open_in_editor(path: &Path) -> EResult<()> {
+    let Some(editor) = env::var("VISUAL").or_else(|_| env::var("EDITOR")).ok().filter(|e| !e.trim().is_empty()) else {
+        if !utils::file_exists(path) {
+            return Err(Failure::NotFound
+                .details(format!("File {} doesn't exist", path.display()))
+                .suggestion("Set `EDITOR` environment variable to create it in the editor")
+                .into());
+        }
+
+        return open_in_file_manager(path);
+    };
+
+    log::info!("Opening {} with {editor}", path.display());
+
+    // editor can come with its own arguments, like `code --wait`
+    let mut words = editor.split_whitespace();
+    let program = words.next().context("Editor is empty")?;
+
+    let status = process::Command::new(program)
+        .args(words)
+        .arg(path)
+        .status()
+        .with_context(|| format!("Failed to start the editor {program}"))?;
+
+    if !status.success() {
+        log::warn!("Editor exited with {status}");
+    }
+
+    Ok(())
+}
+
+/// Open the path with the system's default application for it
+fn open_in_file_manager(path: &Path) -> EResult<()> {
+    let opener = if cfg!(target_os = "windows") {
+        "explorer"
+    } else if cfg!(target_os = "macos") {
+        "open"
+    } else {
+        "xdg-open"
+    };
+
+    log::info!("Opening {} with {opener}", path.display());
+
+    // don't wait for it, file manager can stay open for however long
+    process::Command::new(opener)
+        .arg(path)
+        .spawn()
+        .with_context(|| format!("Failed to start {opener}"))?;
+
+    Ok(())
+}
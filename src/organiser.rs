@@ -1,156 +1,142 @@
-use clap::Args;
-use eyre::Context;
-use eyre::ContextCompat;
-use eyre::Result as EResult;
-use serde_json::Value;
 use std::cmp::Ordering;
-use std::fs::{self, File};
-use std::io::BufWriter;
-use std::mem::take;
-use tap::Tap;
 
-use crate::utils::{self, JArr, JObj, ObjExt, SaveDirHandler};
+use crate::save::{SaveData, COSMETICS_LISTS};
+use crate::utils::{self, SaveDirHandler};
 
+#[cfg(feature = "cli")]
+use {
+    crate::output,
+    crate::save::SaveFile,
+    clap::Args,
+    eyre::{Context, Result as EResult},
+};
+
+#[cfg(feature = "cli")]
 #[derive(Args)]
 #[derive(Debug)]
 pub struct Ops {
     /// Save slot number (0-3)
-    save_slot: u8,
+    #[arg(required_unless_present = "all")]
+    save_slot: Option<u8>,
+    /// Organise every existing save slot
+    #[arg(short = 'a', long, conflicts_with = "save_slot")]
+    all: bool,
 }
 
+#[cfg(feature = "cli")]
 pub fn handler(ops: Ops, mut save_dir: SaveDirHandler) -> EResult<()> {
     log::info!("Organising various messes inside the save file");
 
-    // ======== Read input
+    let slots = match ops.save_slot {
+        Some(slot) => vec![slot],
+        None => existing_slots(&mut save_dir)?,
+    };
 
-    let save_file = save_dir.resolve_save_slot(ops.save_slot)?;
-    log::info!("Reading save file {}", save_file.display());
-    let mut save_json = utils::read_json_file(&save_file).context("Failed to open save file")?;
+    let progress = output::progress_bar(slots.len() as u64, "Organising saves");
 
-    let save_data = save_json
-        .as_object_mut()
-        .context("Invalid save file: not a JSON object")?
-        .get_obj_mut(utils::SAVE_DATA_KEY)?;
+    for slot in slots {
+        organise_slot(&mut save_dir, slot).with_context(|| format!("Failed to organise save slot {slot}"))?;
 
+        progress.inc(1);
+    }
 
-    // ======== Stuff
+    progress.finish_and_clear();
 
-    sort_cosmetics(save_data).context("Failed to sort cosmetics")?;
-    sort_furniture(save_data).context("Failed to sort furniture")?;
-    deduplicate_emails(save_data).context("Failed to deduplicate emails")?;
+    log::info!("Finished organising");
 
-    // ======== Write output
+    Ok(())
+}
 
-    let output_tmp = utils::with_added_extension(&save_file, "new");
-    let output_file = File::create(&output_tmp).context("Failed to create output file")?;
-    serde_json::to_writer_pretty(BufWriter::new(output_file), &save_json).context("Failed to write output JSON to file")?;
+/// Slots of the save dir that have a save in them
+pub fn existing_slots(save_dir: &mut SaveDirHandler) -> crate::Result<Vec<u8>> {
+    let mut slots = Vec::new();
 
-    fs::rename(&save_file, utils::with_added_extension(&save_file, "bak"))
-        .context("Failed to make backup of the original save")?;
-    fs::rename(&output_tmp, &save_file).context("Failed to rename output file to replace input")?;
+    for slot in 0..=3 {
+        if utils::file_exists(&save_dir.resolve_save_slot(slot)?) {
+            slots.push(slot);
+        } else {
+            log::info!("Save slot {slot} is empty, skipping");
+        }
+    }
 
-    log::info!("Finished organising");
+    Ok(slots)
+}
+
+#[cfg(feature = "cli")]
+pub(crate) fn organise_slot(save_dir: &mut SaveDirHandler, save_slot: u8) -> EResult<()> {
+    // ======== Read input
+
+    let mut save = SaveFile::open(save_dir, save_slot)?;
+    let mut save_data = save.typed_data()?;
+
+    // ======== Stuff
+
+    organise(&mut save_data);
+
+    // ======== Write output
+
+    save.set_typed_data(save_data)?;
+    save.write()?;
 
     Ok(())
 }
 
-fn sort_cosmetics(save_data: &mut JObj) -> EResult<()> {
-    const COSMETICS_LISTS: [(&str, &str); 5] = [
-        ("hairlist", "Hair"),
-        ("facelist", "Face"),
-        ("jewllist", "Accessory"),
-        ("shirtlist", "Shirt"),
-        ("jacketlist", "Jacket"),
-    ];
+/// Do all of the organising: [sort cosmetics](sort_cosmetics), [sort furniture](sort_furniture) and
+/// [deduplicate emails](deduplicate_emails)
+pub fn organise(save_data: &mut SaveData) {
+    sort_cosmetics(save_data);
+    sort_furniture(save_data);
+    deduplicate_emails(save_data);
+}
 
+/// Sort the lists of owned wardrobe items alphabetically
+pub fn sort_cosmetics(save_data: &mut SaveData) {
     log::info!("Sorting wardrobe items");
 
-    for (name, label) in COSMETICS_LISTS {
+    for ((_, label), list) in COSMETICS_LISTS.into_iter().zip(save_data.cosmetics_mut()) {
         log::info!("  Sorting {label}");
 
-        let list = save_data.get_arr_mut(name)?;
-
-        let sorted = list
-            .iter()
-            .map(|val| {
-                val.as_str()
-                    .with_context(|| format!("Expected a string, got: {val:#?}"))
-                    .map(String::from)
-            })
-            .collect::<EResult<Vec<String>>>()
-            .with_context(|| format!("Key {name}: failed to parse array element"))?
-            .tap_mut(|list| list.sort())
-            .into_iter()
-            .map(Value::String)
-            .collect::<JArr>();
-
-        *list = sorted;
+        list.sort();
     }
 
     log::info!("Sorting wardrobe items: done");
-
-    Ok(())
 }
 
-fn sort_furniture(save_data: &mut JObj) -> EResult<()> {
+/// Sort owned furniture by name, keeping the computer and the journal ([`FURN_FIXED`]) first
+pub fn sort_furniture(save_data: &mut SaveData) {
     log::info!("Sorting furniture items");
 
-    let list = save_data.get_arr_mut("furnlist")?;
-
-    let sorted: Vec<_> = take(list)
-        .into_iter()
-        .map(|val| -> EResult<(Value, FurnLabel)> {
-            let name = val
-                .as_object()
-                .with_context(|| format!("Expected an object, got: {val:#?}"))?
-                .get_str("name")?
-                .to_string();
-
-            Ok((val, FurnLabel(name)))
-        })
-        .collect::<EResult<Vec<_>>>()
-        .context("Failed to parse furniture list")?
-        .tap_mut(|vec| vec.sort_by(|(_, first), (_, second)| furn_label_cmp(first, second)))
-        .into_iter()
-        .map(|(val, _)| val)
-        .collect();
-
-    *list = sorted;
+    save_data.furniture.sort_by(|first, second| furn_name_cmp(&first.name, &second.name));
 
     log::info!("Sorting furniture items: done");
-
-    Ok(())
 }
 
-struct FurnLabel(String);
-
-fn furn_label_cmp(first: &FurnLabel, second: &FurnLabel) -> Ordering {
-    let i1 = FURN_FIXED.iter().position(|e| e == &first.0);
-    let i2 = FURN_FIXED.iter().position(|e| e == &second.0);
+fn furn_name_cmp(first: &str, second: &str) -> Ordering {
+    let i1 = FURN_FIXED.iter().position(|e| *e == first);
+    let i2 = FURN_FIXED.iter().position(|e| *e == second);
 
     match (i1, i2) {
         (Some(i1), Some(i2)) => i1.cmp(&i2),
         (Some(_), _) => Ordering::Less,
         (_, Some(_)) => Ordering::Greater,
-        _ => first.0.cmp(&second.0),
+        _ => first.cmp(second),
     }
 }
 
-const FURN_FIXED: [&str; 2] = ["computer1", "hc_journal"];
+/// Furniture that is always at the start of the list
+pub const FURN_FIXED: [&str; 2] = ["computer1", "hc_journal"];
 
-fn deduplicate_emails(save_data: &mut JObj) -> EResult<()> {
+/// Remove emails that were received more than once, keeping the earliest
+///
+/// Read and unread emails are checked together, so an email that is in both is only kept as read
+pub fn deduplicate_emails(save_data: &mut SaveData) {
     let mut email_ids: Vec<i64> = Vec::with_capacity(32);
     let mut removed = 0;
 
-    let mut dedup_op = |name: &str| -> EResult<()> {
-        let emails = save_data.get_arr_mut(name)?;
-
+    let mut dedup_op = |emails: &mut Vec<i64>| {
         // emails are stored in the same way they are shown in-game: newer first
         for i in (0..emails.len()).rev() {
-            let val = &emails[i];
-            let id = val
-                .as_i64()
-                .with_context(|| format!("Expected an int, got: {val:#?}"))?;
+            let id = emails[i];
 
             if email_ids.contains(&id) {
                 emails.remove(i);
@@ -159,20 +145,16 @@ fn deduplicate_emails(save_data: &mut JObj) -> EResult<()> {
                 email_ids.push(id);
             }
         }
-
-        Ok(())
     };
 
     log::info!("Deduplicating emails");
 
-    dedup_op("emailreadlist")?;
-    dedup_op("emailunreadlist")?;
+    dedup_op(&mut save_data.read_emails);
+    dedup_op(&mut save_data.unread_emails);
 
     if removed != 0 {
         log::info!("Removed {removed} duplicated emails");
     }
 
     log::info!("Deduplicating emails: done");
-
-    Ok(())
 }
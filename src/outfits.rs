@@ -1,18 +1,27 @@
-use clap::{Args, Subcommand};
 use eyre::Context;
 use eyre::Result as EResult;
-use eyre::{eyre, ContextCompat};
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
 use std::collections::HashMap;
 use std::fmt::{Display, Write};
-use std::fs::{self, File};
-use std::io::BufWriter;
-use std::path::{Path, PathBuf};
-use tap::Tap;
+use std::path::Path;
 
-use crate::utils::{self, ObjExt, SaveDirHandler};
+use crate::exit::Failure;
+use crate::save::Wardrobe;
+use crate::utils;
 
+#[cfg(feature = "cli")]
+use {
+    crate::i18n::tr,
+    crate::output,
+    crate::save::PartialSave,
+    crate::utils::SaveDirHandler,
+    clap::{Args, Subcommand},
+    std::path::PathBuf,
+};
+
+pub const OUTFITS_FILE: &str = "outfits.json";
+
+#[cfg(feature = "cli")]
 #[derive(Args)]
 #[derive(Debug)]
 pub struct Ops {
@@ -26,6 +35,7 @@ pub struct Ops {
     action: Cmd,
 }
 
+#[cfg(feature = "cli")]
 #[derive(Subcommand)]
 #[derive(Debug)]
 enum Cmd {
@@ -61,6 +71,7 @@ enum Cmd {
     },
 }
 
+#[cfg(feature = "cli")]
 pub fn handler(ops: Ops, mut save_dir: SaveDirHandler) -> EResult<()> {
     log::info!("Working with outfits");
 
@@ -68,10 +79,8 @@ pub fn handler(ops: Ops, mut save_dir: SaveDirHandler) -> EResult<()> {
         path
     } else {
         save_dir
-            .get_save_dir()
+            .resolve_file(OUTFITS_FILE)
             .context("Save dir not found and no custom path to outfits file was provided")?
-            .to_owned()
-            .tap_mut(|p| p.push("outfits.json"))
     };
 
     log::info!("Using outfit file: {}", outfits_file.display());
@@ -91,18 +100,20 @@ pub fn handler(ops: Ops, mut save_dir: SaveDirHandler) -> EResult<()> {
     Ok(())
 }
 
+#[cfg(feature = "cli")]
 fn list_outfits(outfits_path: &Path) -> EResult<()> {
-    let storage = read_outfits(outfits_path, false)?;
-
-    storage
-        .outfits
-        .iter()
-        .for_each(|(name, outfit)| println!("{name}\t{outfit}"));
+    let storage = OutfitsStorage::read(outfits_path)?;
 
-    Ok(())
+    output::print(&storage.outfits, || {
+        storage
+            .outfits
+            .iter()
+            .for_each(|(name, outfit)| println!("{name}\t{outfit}"));
+    })
 }
 
-fn save_outfit(
+#[cfg(feature = "cli")]
+pub(crate) fn save_outfit(
     outfits_path: &Path,
     outfit_name: String,
     save_dir: &mut SaveDirHandler,
@@ -112,48 +123,34 @@ fn save_outfit(
     log::info!("Saving outfit");
 
     if outfit_name == "default" {
-        return Err(eyre!("Name \"default\" is reserved for starting outfit"));
+        return Err(Failure::Invalid.error("Name \"default\" is reserved for starting outfit"));
     }
 
     // ======== Read input
 
-    let save_file = save_dir.resolve_save_slot(save_slot)?;
-    log::info!("Reading save file {save_slot}");
-    let save_json = utils::read_json_file(&save_file).context("Failed to open save file")?;
+    let save = PartialSave::open(save_dir, save_slot, &Wardrobe::KEYS)?;
+    let wardrobe = save.typed_data()?;
 
-    let save_data = save_json
-        .as_object()
-        .context("Invalid save file: not a JSON object")?
-        .get_obj(utils::SAVE_DATA_KEY)?;
-
-    let mut storage = read_outfits(outfits_path, false)?;
+    let mut storage = OutfitsStorage::read(outfits_path)?;
     let existing = storage.outfits.get(&outfit_name);
 
     // ======== Getting outfit
 
-    let get_part = |name: &str, label: &str, field: fn(&Outfit) -> Option<&str>| -> EResult<Option<String>> {
-        let value = save_data
-            .get_str(name)
-            .with_context(|| format!("Failed to get {label}"))?;
-
-        let out = if !partial || existing.is_none() || existing.and_then(field).is_some() {
-            log::info!("{label} value: \"{value}\"");
-            Some(value.to_string())
-        } else {
-            log::info!("{label} value: \"{value}\" (skipping)");
-            None
-        };
+    let mut outfit = Outfit::worn(&wardrobe);
 
-        Ok(out)
-    };
+    if let Some(existing) = existing.filter(|_| partial) {
+        log::info!("Only saving the parts already in the outfit");
 
-    let hair = get_part("hairon", "Hair", |e| e.hair.as_deref())?;
-    let face = get_part("faceon", "Face", |e| e.face.as_deref())?;
-    let accessory = get_part("jewlon", "Accessory", |e| e.accessory.as_deref())?;
-    let shirt = get_part("shirton", "Shirt", |e| e.shirt.as_deref())?;
-    let jacket = get_part("jacketon", "Jacket", |e| e.jacket.as_deref())?;
+        outfit.hair = outfit.hair.filter(|_| existing.hair.is_some());
+        outfit.face = outfit.face.filter(|_| existing.face.is_some());
+        outfit.accessory = outfit.accessory.filter(|_| existing.accessory.is_some());
+        outfit.shirt = outfit.shirt.filter(|_| existing.shirt.is_some());
+        outfit.jacket = outfit.jacket.filter(|_| existing.jacket.is_some());
+    }
 
-    let outfit = Outfit { hair, face, accessory, shirt, jacket };
+    if existing.is_some() && !utils::confirm(&tr!("confirm-overwrite-outfit", name = outfit_name))? {
+        return Err(Failure::WriteRefused.error("Cancelled"));
+    }
 
     log::info!("Saved the outfit \"{outfit_name}\": {outfit}");
 
@@ -161,16 +158,15 @@ fn save_outfit(
 
     // ======== Write output
 
-    let output_file = File::create(outfits_path).context("Failed to write to outfits file")?;
-    serde_json::to_writer_pretty(BufWriter::new(output_file), &storage)
-        .context("Failed to write output JSON to file")?;
+    storage.write(outfits_path)?;
 
     log::info!("Saved outfits file");
 
     Ok(())
 }
 
-fn load_outfit(
+#[cfg(feature = "cli")]
+pub(crate) fn load_outfit(
     outfits_path: &Path,
     outfit_name: &str,
     save_dir: &mut SaveDirHandler,
@@ -181,88 +177,47 @@ fn load_outfit(
 
     // ======== Read input
 
-    let save_file = save_dir.resolve_save_slot(save_slot)?;
-    log::info!("Reading save file {}", save_file.display());
-    let mut save_json = utils::read_json_file(&save_file).context("Failed to open save file")?;
-
-    let save_data = save_json
-        .as_object_mut()
-        .context("Invalid save file: not a JSON object")?
-        .get_obj_mut(utils::SAVE_DATA_KEY)?;
+    let mut save = PartialSave::open(save_dir, save_slot, &Wardrobe::KEYS)?;
+    let mut wardrobe = save.typed_data()?;
 
     let outfit = if outfit_name == "default" {
         log::info!("Using default outfit");
 
-        Outfit::default()
+        Outfit::starting()
     } else {
-        read_outfits(outfits_path, false)?
+        OutfitsStorage::read(outfits_path)?
             .outfits
             .remove(outfit_name)
-            .ok_or_else(|| eyre!("Outfit \"{outfit_name}\" not found"))?
+            .ok_or_else(|| {
+                Failure::NotFound
+                    .details(format!("Outfit \"{outfit_name}\" not found"))
+                    .suggestion("Use `outfits list` to see the saved outfits")
+            })?
     };
 
     // ======== Setting outfit
 
-    let mut set_part = |name: &str, list_name: &str, label: &str, value: Option<String>| -> EResult<()> {
-        let Some(value) = value else {
-            log::info!("{label}: skip");
-            return Ok(());
-        };
-
-        let owned = save_data
-            .get_arr(list_name)?
-            .iter()
-            .map(|val| {
-                val.as_str()
-                    .with_context(|| format!("Expected a string, got: {val:#?}"))
-                    .map(String::from)
-            })
-            .collect::<EResult<Vec<String>>>()
-            .with_context(|| format!("Key {name}: failed to parse array element"))?
-            .into_iter()
-            .any(|val| val == value);
-
-        if !owned {
-            if partial {
-                log::warn!("{label}: value \"{value}\" is not owned, skipping");
-                return Ok(());
-            } else {
-                return Err(eyre!("{label}: value \"{value}\" is not owned"));
-            }
-        }
-
-        log::info!("{label}: setting value \"{value}\"");
-        save_data.insert(name.to_string(), Value::String(value));
-
-        Ok(())
-    };
-
-    set_part("hairon", "hairlist", "Hair", outfit.hair)?;
-    set_part("faceon", "facelist", "Face", outfit.face)?;
-    set_part("jewlon", "jewllist", "Accessory", outfit.accessory)?;
-    set_part("shirton", "shirtlist", "Shirt", outfit.shirt)?;
-    set_part("jacketon", "jacketlist", "Jacket", outfit.jacket)?;
+    outfit.put_on(&mut wardrobe, partial)?;
 
     // ======== Write output
 
-    let output_tmp = utils::with_added_extension(&save_file, "new");
-    let output_file = File::create(&output_tmp).context("Failed to create output file")?;
-    serde_json::to_writer_pretty(BufWriter::new(output_file), &save_json)
-        .context("Failed to write output JSON to file")?;
-
-    fs::rename(&save_file, utils::with_added_extension(&save_file, "bak"))
-        .context("Failed to make backup of the original save")?;
-    fs::rename(&output_tmp, &save_file).context("Failed to rename output file to replace input")?;
+    save.set_typed_data(wardrobe)?;
+    save.write()?;
 
     log::info!("Finished loading outfit");
 
     Ok(())
 }
 
+/// Names of the outfits saved in the file, which has to exist
+pub fn outfit_names(path: &Path) -> crate::Result<Vec<String>> {
+    Ok(read_outfits(path, true)?.outfits.into_keys().collect())
+}
+
 fn read_outfits(path: &Path, require: bool) -> EResult<OutfitsStorage> {
-    if !path.exists() {
+    if !utils::file_exists(path) {
         if require {
-            return Err(eyre!("Outfits file doesn't exist"));
+            return Err(Failure::NotFound.error("Outfits file doesn't exist"));
         } else {
             log::info!("Outfits file doesn't exist");
 
@@ -280,23 +235,27 @@ fn read_outfits(path: &Path, require: bool) -> EResult<OutfitsStorage> {
     Ok(storage)
 }
 
+/// Worn wardrobe items, by their in-game names
+///
+/// Parts that aren't set are left as they are when the outfit is put on
 #[derive(Serialize, Deserialize)]
 #[derive(Debug)]
-struct Outfit {
+pub struct Outfit {
     #[serde(skip_serializing_if = "Option::is_none")]
-    hair: Option<String>,
+    pub hair: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    face: Option<String>,
+    pub face: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    accessory: Option<String>,
+    pub accessory: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    shirt: Option<String>,
+    pub shirt: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    jacket: Option<String>,
+    pub jacket: Option<String>,
 }
 
 impl Outfit {
-    fn default() -> Self {
+    /// Outfit the game starts with
+    pub fn starting() -> Self {
         Self {
             hair: Some("a".to_string()),
             face: Some("aa".to_string()),
@@ -305,6 +264,56 @@ impl Outfit {
             jacket: Some("a".to_string()),
         }
     }
+
+    /// Outfit currently worn in the save
+    pub fn worn(wardrobe: &Wardrobe) -> Self {
+        Self {
+            hair: Some(wardrobe.hair_worn.clone()),
+            face: Some(wardrobe.face_worn.clone()),
+            accessory: Some(wardrobe.accessory_worn.clone()),
+            shirt: Some(wardrobe.shirt_worn.clone()),
+            jacket: Some(wardrobe.jacket_worn.clone()),
+        }
+    }
+
+    /// Put on the parts of the outfit that are set
+    ///
+    /// Fails if the save doesn't own one of them, unless `partial` - then those are skipped instead
+    pub fn put_on(self, data: &mut Wardrobe, partial: bool) -> crate::Result<()> {
+        let set_part =
+            |worn: &mut String, owned: &[String], list: &str, label: &str, value: Option<String>| -> crate::Result<()> {
+                let Some(value) = value else {
+                    log::info!("{label}: skip");
+                    return Ok(());
+                };
+
+                if !owned.contains(&value) {
+                    if partial {
+                        log::warn!("{label}: value \"{value}\" is not owned, skipping");
+                        return Ok(());
+                    } else {
+                        return Err(Failure::NotFound
+                            .details(format!("{label}: value \"{value}\" is not owned"))
+                            .key(list)
+                            .suggestion("Use `--partial` to load the rest of the outfit")
+                            .into());
+                    }
+                }
+
+                log::info!("{label}: setting value \"{value}\"");
+                *worn = value;
+
+                Ok(())
+            };
+
+        set_part(&mut data.hair_worn, &data.hair_owned, "hairlist", "Hair", self.hair)?;
+        set_part(&mut data.face_worn, &data.face_owned, "facelist", "Face", self.face)?;
+        set_part(&mut data.accessory_worn, &data.accessory_owned, "jewllist", "Accessory", self.accessory)?;
+        set_part(&mut data.shirt_worn, &data.shirt_owned, "shirtlist", "Shirt", self.shirt)?;
+        set_part(&mut data.jacket_worn, &data.jacket_owned, "jacketlist", "Jacket", self.jacket)?;
+
+        Ok(())
+    }
 }
 
 impl Display for Outfit {
@@ -336,8 +345,21 @@ impl Display for Outfit {
     }
 }
 
+/// Contents of the outfits file
 #[derive(Serialize, Deserialize)]
 #[derive(Debug)]
-struct OutfitsStorage {
-    outfits: HashMap<String, Outfit>,
+pub struct OutfitsStorage {
+    /// Outfits by their names
+    pub outfits: HashMap<String, Outfit>,
+}
+
+impl OutfitsStorage {
+    /// Read the outfits file, with no outfits in it if it doesn't exist
+    pub fn read(path: &Path) -> crate::Result<Self> {
+        Ok(read_outfits(path, false)?)
+    }
+
+    pub fn write(&self, path: &Path) -> crate::Result<()> {
+        Ok(utils::write_json_file(path, self).context("Failed to write to outfits file")?)
+    }
 }
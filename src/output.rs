@@ -0,0 +1,142 @@
+use clap::{ColorChoice, ValueEnum};
+use eyre::{Context, Result as EResult};
+use indicatif::{ProgressBar, ProgressStyle};
+use serde::Serialize;
+use serde_json::Value;
+use std::env;
+use std::io::{self, IsTerminal, Write};
+use std::sync::OnceLock;
+
+use crate::utils::JObj;
+
+static FORMAT: OnceLock<Format> = OnceLock::new();
+
+/// Format of the command output printed to stdout
+///
+/// Logs and any other human-oriented messages always go to stderr
+#[derive(ValueEnum)]
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub enum Format {
+    /// Plain text, mostly tab-separated
+    #[default]
+    Text,
+    /// JSON, for use by other tools
+    Json,
+    /// Tab-separated values with a header, for `cut`, `awk` and such
+    ///
+    /// Columns are in alphabetical order of their JSON names
+    Tsv,
+}
+
+pub fn set_format(format: Format) {
+    if FORMAT.set(format).is_err() {
+        log::warn!("Output format was already set");
+    }
+}
+
+pub fn format() -> Format {
+    FORMAT.get().copied().unwrap_or_default()
+}
+
+/// Print the result of a command, either with the `text` printer, as JSON or TSV
+pub fn print(value: &impl Serialize, text: impl FnOnce()) -> EResult<()> {
+    match format() {
+        Format::Text => text(),
+        Format::Json => {
+            let mut stdout = io::stdout().lock();
+
+            serde_json::to_writer_pretty(&mut stdout, value).context("Failed to print JSON output")?;
+            writeln!(stdout).context("Failed to print JSON output")?;
+        }
+        Format::Tsv => {
+            let value = serde_json::to_value(value).context("Failed to convert output to JSON")?;
+            let mut stdout = io::stdout().lock();
+
+            for row in tsv_rows(value) {
+                match writeln!(stdout, "{}", row.join("\t")) {
+                    // reader (like `head`) doesn't want the rest
+                    Err(err) if err.kind() == io::ErrorKind::BrokenPipe => break,
+                    result => result.context("Failed to print TSV output")?,
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Lay the value out as a table, with the header as the first row
+///
+/// - list of objects is a row per object
+/// - object of objects (like outfits by their name) is the same, with the key in the `name` column
+/// - object of anything else is a row per key, with the key and the value
+/// - anything else is a row per item, or just a single one
+fn tsv_rows(value: Value) -> Vec<Vec<String>> {
+    let objects: Vec<JObj> = match value {
+        Value::Array(arr) if arr.iter().all(Value::is_object) => {
+            arr.into_iter().filter_map(|val| val.as_object().cloned()).collect()
+        }
+        Value::Object(obj) if !obj.is_empty() && obj.values().all(Value::is_object) => obj
+            .into_iter()
+            .filter_map(|(key, val)| {
+                let mut row = val.as_object()?.clone();
+                row.insert("name".to_string(), Value::String(key));
+                Some(row)
+            })
+            .collect(),
+        Value::Object(obj) => {
+            return obj.into_iter().map(|(key, val)| vec![tsv_cell(&Value::String(key)), tsv_cell(&val)]).collect()
+        }
+        Value::Array(arr) => return arr.iter().map(|val| vec![tsv_cell(val)]).collect(),
+        val => return vec![vec![tsv_cell(&val)]],
+    };
+
+    let mut columns: Vec<&String> = objects.iter().flat_map(|obj| obj.keys()).collect();
+    columns.sort();
+    columns.dedup();
+
+    let mut rows = vec![columns.iter().map(|c| c.to_string()).collect()];
+    rows.extend(
+        objects
+            .iter()
+            .map(|obj| columns.iter().map(|c| obj.get(*c).map(tsv_cell).unwrap_or_default()).collect()),
+    );
+
+    rows
+}
+
+/// Single value in a TSV table, with anything that would break the table escaped
+fn tsv_cell(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.replace('\\', "\\\\").replace('\t', "\\t").replace('\n', "\\n").replace('\r', "\\r"),
+        // lists and objects are kept as compact JSON
+        val => val.to_string(),
+    }
+}
+
+/// Whether the output should be colored
+///
+/// `auto` follows the [NO_COLOR](https://no-color.org) convention, and only colors the output going to a terminal
+pub fn use_color(choice: ColorChoice) -> bool {
+    match choice {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto => env::var_os("NO_COLOR").is_none_or(|v| v.is_empty()) && io::stderr().is_terminal(),
+    }
+}
+
+/// Progress bar for operations going over multiple things
+///
+/// Bar is drawn to stderr only when it's a terminal, and never in `--quiet` mode
+pub fn progress_bar(len: u64, message: &'static str) -> ProgressBar {
+    if log::max_level() < log::LevelFilter::Info {
+        return ProgressBar::hidden();
+    }
+
+    let style = ProgressStyle::with_template("{msg} [{bar:30}] {pos}/{len}")
+        .expect("Progress bar template should be valid")
+        .progress_chars("=> ");
+
+    ProgressBar::new(len).with_style(style).with_message(message)
+}
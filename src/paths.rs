@@ -0,0 +1,58 @@
+use clap::Args;
+use eyre::Result as EResult;
+use serde::Serialize;
+use std::path::PathBuf;
+
+use crate::config::Config;
+use crate::i18n::tr;
+use crate::outfits::OUTFITS_FILE;
+use crate::output;
+use crate::utils::SaveDirHandler;
+
+#[derive(Args)]
+#[derive(Debug)]
+pub struct Ops {}
+
+pub fn handler(_ops: Ops, mut save_dir: SaveDirHandler) -> EResult<()> {
+    let overridden = save_dir.is_overridden();
+
+    // still print the rest, not finding the save dir is likely the reason for asking in the first place
+    let dir = match save_dir.get_save_dir() {
+        Ok(dir) => Some(dir.to_owned()),
+        Err(err) => {
+            log::warn!("{err:#}");
+            None
+        }
+    };
+
+    let paths = Paths {
+        save_dir_found_by: if overridden { "--save-dir" } else { "default location" },
+        outfits: dir.as_ref().map(|d| d.join(OUTFITS_FILE)),
+        // backups are kept next to the saves they are of
+        backups: dir.clone(),
+        config: Config::path(),
+        save_dir: dir,
+    };
+
+    output::print(&paths, || {
+        let show = |path: &Option<PathBuf>| path.as_ref().map_or("-".to_string(), |p| p.display().to_string());
+
+        let found_by = if overridden { paths.save_dir_found_by.to_string() } else { tr!("label-default-location") };
+
+        println!("{}\t{} ({found_by})", tr!("label-save-dir"), show(&paths.save_dir));
+        println!("{}\t{}", tr!("label-outfits"), show(&paths.outfits));
+        println!("{}\t{}", tr!("label-backups"), show(&paths.backups));
+        println!("{}\t{}", tr!("label-config"), show(&paths.config));
+    })
+}
+
+#[derive(Serialize)]
+#[derive(Debug)]
+struct Paths {
+    /// `null` if it couldn't be found
+    save_dir: Option<PathBuf>,
+    save_dir_found_by: &'static str,
+    outfits: Option<PathBuf>,
+    backups: Option<PathBuf>,
+    config: Option<PathBuf>,
+}
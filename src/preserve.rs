@@ -0,0 +1,610 @@
+//! Writing JSON back with the values that weren't changed exactly as they were read
+//!
+//! Parsing normalizes the numbers (`300` read into a float is written as `300.0`, `1e3` as `1000.0`, etc.), which the
+//! game may care about, and which turns a small change into a diff of the whole file
+//!
+//! The text that was read is [scanned](scan) once for where each value in it starts and ends, without parsing
+//! anything into values. Those spans are then compared with the values as they were read, bottom-up, and reused
+//! for everything that is still the same
+//!
+//! What is kept is the text of each unchanged value, as a whole: numbers as they were written, the order of the
+//! fields, escapes in the strings, and the whitespace inside it. Objects and arrays with a change somewhere in them
+//! are written anew in the output's format (serde's pretty one for the saves), with only their unchanged values
+//! pasted in as they were - keeping their old indentation, even if it's now nested at a different depth. So the
+//! whole file is only byte-identical if nothing was changed (apart from the whitespace around the top value), and
+//! only looks the same as before around the changes if it was already in serde's pretty format
+
+use serde::ser::{Error as _, SerializeMap, SerializeSeq};
+use serde::{Serialize, Serializer};
+use serde_json::value::RawValue;
+use serde_json::Value;
+use std::borrow::Cow;
+use std::collections::{HashMap, VecDeque};
+use std::fmt::{self, Display};
+
+use crate::utils::JObj;
+
+/// `value` to write, reusing the text of `source` for everything that is the same as in `original` (which was read
+/// from `source`)
+///
+/// Falls back to writing `value` as is if `source` isn't valid JSON
+pub fn preserving<'a>(value: &'a Value, original: &Value, source: &'a [u8]) -> Preserved<'a> {
+    match scan(source) {
+        Ok(node) => changed(value, original, &node).unwrap_or(Preserved::Raw(node.raw)),
+        Err(err) => {
+            log::debug!("Unable to reuse the original formatting: {err}");
+
+            Preserved::Value(value)
+        }
+    }
+}
+
+/// Fields `keys` of the object under `key` in `source`, without parsing anything else into values
+///
+/// `None` if there's no such object
+pub fn extract(source: &[u8], key: &str, keys: &[&str]) -> Result<Option<JObj>, ScanError> {
+    let Kind::Object(top) = scan(source)?.kind else {
+        return Ok(None);
+    };
+
+    let Some((_, Node { kind: Kind::Object(fields), .. })) = top.into_iter().find(|(name, _)| name == key) else {
+        return Ok(None);
+    };
+
+    fields
+        .into_iter()
+        .filter(|(name, _)| keys.contains(&name.as_ref()))
+        .map(|(name, node)| Ok((name.into_owned(), serde_json::from_str(node.raw)?)))
+        .collect::<Result<JObj, ScanError>>()
+        .map(Some)
+}
+
+/// `source` with the `fields` of the object under `key` replaced (or added), and everything else as it was read
+///
+/// `originals` are the fields as they were [extracted](extract), to tell which of them were changed
+pub fn patching<'a>(
+    source: &'a [u8],
+    key: &str,
+    fields: &'a JObj,
+    originals: &JObj,
+) -> Result<Preserved<'a>, ScanError> {
+    let Kind::Object(top) = scan(source)?.kind else {
+        return Err(ScanError::new(0, "a JSON object"));
+    };
+    let mut patched = Vec::with_capacity(top.len());
+
+    for (name, node) in top {
+        if name != key {
+            patched.push((name, Preserved::Raw(node.raw)));
+            continue;
+        }
+
+        let Kind::Object(raw_fields) = node.kind else {
+            return Err(ScanError::new(0, "save data to be an object"));
+        };
+
+        let mut object: Vec<(Cow<str>, Preserved)> = Vec::with_capacity(raw_fields.len());
+
+        for (field, node) in &raw_fields {
+            let preserved = match fields.get_key_value(field.as_ref()) {
+                Some((field, value)) => {
+                    let preserved = match originals.get(field) {
+                        Some(original) => changed(value, original, node).unwrap_or(Preserved::Raw(node.raw)),
+                        None => Preserved::Value(value),
+                    };
+
+                    (Cow::Borrowed(field.as_str()), preserved)
+                }
+                None => (field.clone(), Preserved::Raw(node.raw)),
+            };
+
+            object.push(preserved);
+        }
+
+        object.extend(
+            fields
+                .iter()
+                .filter(|(field, _)| !raw_fields.iter().any(|(raw_field, _)| raw_field == *field))
+                .map(|(field, value)| (Cow::Borrowed(field.as_str()), Preserved::Value(value))),
+        );
+
+        patched.push((name, Preserved::Object(object)));
+    }
+
+    Ok(Preserved::Object(patched))
+}
+
+/// `value` to write in place of `original`, which was read from `node` - or `None` if it's still the same
+fn changed<'a>(value: &'a Value, original: &Value, node: &Node<'a>) -> Option<Preserved<'a>> {
+    match (value, original, &node.kind) {
+        (Value::Object(fields), Value::Object(originals), Kind::Object(nodes)) => {
+            let mut is_changed = fields.len() != originals.len();
+
+            // fields stay in the same order as they were, with the new ones at the end
+            let mut preserved: Vec<(Cow<str>, Preserved)> = Vec::with_capacity(fields.len());
+
+            for (key, node) in nodes {
+                let Some((key, value)) = fields.get_key_value(key.as_ref()) else {
+                    is_changed = true;
+                    continue;
+                };
+
+                let field = match originals.get(key).map(|original| changed(value, original, node)) {
+                    Some(None) => Preserved::Raw(node.raw),
+                    Some(Some(field)) => {
+                        is_changed = true;
+                        field
+                    }
+                    None => {
+                        is_changed = true;
+                        Preserved::Value(value)
+                    }
+                };
+
+                preserved.push((Cow::Borrowed(key.as_str()), field));
+            }
+
+            for (key, value) in fields {
+                if !nodes.iter().any(|(node_key, _)| node_key == key) {
+                    is_changed = true;
+                    preserved.push((Cow::Borrowed(key.as_str()), Preserved::Value(value)));
+                }
+            }
+
+            is_changed.then_some(Preserved::Object(preserved))
+        }
+        (Value::Array(items), Value::Array(originals), Kind::Array(nodes)) if originals.len() == nodes.len() => {
+            let in_place = items.len() == originals.len()
+                && items.iter().zip(originals).zip(nodes).all(|((item, original), node)| {
+                    changed(item, original, node).is_none()
+                });
+
+            (!in_place).then(|| Preserved::Array(preserve_items(items, originals, nodes)))
+        }
+        _ => (!same(value, original)).then_some(Preserved::Value(value)),
+    }
+}
+
+/// Items may have been added, removed or moved around (e.g. sorted), so each is matched to any unchanged original,
+/// and only the changed ones are compared with the original at the same position
+fn preserve_items<'a>(items: &'a [Value], originals: &[Value], nodes: &[Node<'a>]) -> Vec<Preserved<'a>> {
+    // originals by their key, in order - so an unchanged item is matched to the one at the same position first
+    let mut unused: HashMap<String, VecDeque<usize>> = HashMap::new();
+
+    for (i, original) in originals.iter().enumerate() {
+        unused.entry(key(original)).or_default().push_back(i);
+    }
+
+    let matched: Vec<Option<usize>> =
+        items.iter().map(|item| unused.get_mut(&key(item)).and_then(VecDeque::pop_front)).collect();
+
+    let mut used = vec![false; originals.len()];
+    matched.iter().flatten().for_each(|&i| used[i] = true);
+
+    items
+        .iter()
+        .zip(matched)
+        .enumerate()
+        .map(|(i, (item, matched))| match matched {
+            Some(j) => Preserved::Raw(nodes[j].raw),
+            None if i < nodes.len() && !used[i] && (item.is_object() || item.is_array()) => {
+                changed(item, &originals[i], &nodes[i]).unwrap_or(Preserved::Raw(nodes[i].raw))
+            }
+            None => Preserved::Value(item),
+        })
+        .collect()
+}
+
+/// Same for the values that are [`same`], for finding them without comparing with every other value
+fn key(value: &Value) -> String {
+    fn normalize(value: &Value) -> Value {
+        match value {
+            Value::Number(n) => match (n.as_i64(), n.as_f64()) {
+                (Some(i), _) => Value::from(i),
+                (None, Some(f)) if f.fract() == 0.0 && f.abs() < i64::MAX as f64 => Value::from(f as i64),
+                _ => value.clone(),
+            },
+            Value::Array(items) => items.iter().map(normalize).collect(),
+            Value::Object(fields) => fields.iter().map(|(k, v)| (k.clone(), normalize(v))).collect(),
+            _ => value.clone(),
+        }
+    }
+
+    normalize(value).to_string()
+}
+
+/// Whether the values are the same, counting numbers of different types but same value (`300` and `300.0`) as such
+fn same(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) => match (a.as_i64(), b.as_i64()) {
+            (Some(a), Some(b)) => a == b,
+            _ => a.as_f64() == b.as_f64(),
+        },
+        (Value::Array(a), Value::Array(b)) => a.len() == b.len() && a.iter().zip(b).all(|(a, b)| same(a, b)),
+        (Value::Object(a), Value::Object(b)) => {
+            a.len() == b.len() && a.iter().all(|(key, a)| b.get(key).is_some_and(|b| same(a, b)))
+        }
+        (a, b) => a == b,
+    }
+}
+
+/// Where a value starts and ends in the text it was read from, along with the values inside it
+struct Node<'a> {
+    raw: &'a str,
+    kind: Kind<'a>,
+}
+
+enum Kind<'a> {
+    Scalar,
+    /// Fields in the order they were written in
+    Object(Vec<(Cow<'a, str>, Node<'a>)>),
+    Array(Vec<Node<'a>>),
+}
+
+/// Find the spans of every value in the JSON, in a single pass
+fn scan(source: &[u8]) -> Result<Node<'_>, ScanError> {
+    let text = std::str::from_utf8(source).map_err(|err| ScanError::new(err.valid_up_to(), "valid UTF-8"))?;
+    let mut scanner = Scanner { text, pos: 0 };
+
+    let node = scanner.value()?;
+    scanner.skip_whitespace();
+
+    if scanner.pos != text.len() {
+        return Err(ScanError::new(scanner.pos, "the end of the file"));
+    }
+
+    Ok(node)
+}
+
+struct Scanner<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl<'a> Scanner<'a> {
+    fn value(&mut self) -> Result<Node<'a>, ScanError> {
+        self.skip_whitespace();
+
+        let start = self.pos;
+        let kind = match self.peek() {
+            Some(b'{') => self.object()?,
+            Some(b'[') => self.array()?,
+            Some(b'"') => self.string().map(|()| Kind::Scalar)?,
+            Some(b't') => self.literal("true").map(|()| Kind::Scalar)?,
+            Some(b'f') => self.literal("false").map(|()| Kind::Scalar)?,
+            Some(b'n') => self.literal("null").map(|()| Kind::Scalar)?,
+            Some(b'-' | b'0'..=b'9') => self.number().map(|()| Kind::Scalar)?,
+            _ => return Err(ScanError::new(self.pos, "a value")),
+        };
+
+        Ok(Node { raw: &self.text[start..self.pos], kind })
+    }
+
+    fn object(&mut self) -> Result<Kind<'a>, ScanError> {
+        let mut fields = Vec::new();
+        self.pos += 1;
+        self.skip_whitespace();
+
+        if self.peek() == Some(b'}') {
+            self.pos += 1;
+            return Ok(Kind::Object(fields));
+        }
+
+        loop {
+            self.skip_whitespace();
+
+            let key = self.key()?;
+            self.skip_whitespace();
+            self.expect(b':', "`:`")?;
+
+            fields.push((key, self.value()?));
+            self.skip_whitespace();
+
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b'}') => {
+                    self.pos += 1;
+                    return Ok(Kind::Object(fields));
+                }
+                _ => return Err(ScanError::new(self.pos, "`,` or `}`")),
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Kind<'a>, ScanError> {
+        let mut items = Vec::new();
+        self.pos += 1;
+        self.skip_whitespace();
+
+        if self.peek() == Some(b']') {
+            self.pos += 1;
+            return Ok(Kind::Array(items));
+        }
+
+        loop {
+            items.push(self.value()?);
+            self.skip_whitespace();
+
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b']') => {
+                    self.pos += 1;
+                    return Ok(Kind::Array(items));
+                }
+                _ => return Err(ScanError::new(self.pos, "`,` or `]`")),
+            }
+        }
+    }
+
+    /// Key of a field, only unescaped if it has escapes in it
+    fn key(&mut self) -> Result<Cow<'a, str>, ScanError> {
+        if self.peek() != Some(b'"') {
+            return Err(ScanError::new(self.pos, "a key"));
+        }
+
+        let start = self.pos;
+        self.string()?;
+        let raw = &self.text[start..self.pos];
+
+        if raw.contains('\\') {
+            Ok(Cow::Owned(serde_json::from_str(raw)?))
+        } else {
+            Ok(Cow::Borrowed(&raw[1..raw.len() - 1]))
+        }
+    }
+
+    fn string(&mut self) -> Result<(), ScanError> {
+        let start = self.pos;
+        self.pos += 1;
+
+        while let Some(byte) = self.peek() {
+            match byte {
+                b'"' => {
+                    self.pos += 1;
+                    return Ok(());
+                }
+                b'\\' => self.pos += 2,
+                0..=0x1f => return Err(ScanError::new(self.pos, "no control characters in a string")),
+                _ => self.pos += 1,
+            }
+        }
+
+        Err(ScanError::new(start, "the string to end"))
+    }
+
+    /// Number as JSON has them: no leading zeroes, and digits on both sides of the `.`
+    fn number(&mut self) -> Result<(), ScanError> {
+        let start = self.pos;
+
+        if self.peek() == Some(b'-') {
+            self.pos += 1;
+        }
+
+        match self.peek() {
+            Some(b'0') => self.pos += 1,
+            Some(b'1'..=b'9') => self.digits(),
+            _ => return Err(ScanError::new(start, "a number")),
+        }
+
+        if self.peek() == Some(b'.') {
+            self.pos += 1;
+            self.expect_digits(start)?;
+        }
+
+        if let Some(b'e' | b'E') = self.peek() {
+            self.pos += 1;
+
+            if let Some(b'+' | b'-') = self.peek() {
+                self.pos += 1;
+            }
+
+            self.expect_digits(start)?;
+        }
+
+        // `01` would otherwise be scanned as `0` followed by garbage, which is less clear of an error
+        if let Some(b'0'..=b'9' | b'.' | b'e' | b'E' | b'+' | b'-') = self.peek() {
+            return Err(ScanError::new(start, "a number"));
+        }
+
+        Ok(())
+    }
+
+    fn expect_digits(&mut self, start: usize) -> Result<(), ScanError> {
+        if !matches!(self.peek(), Some(b'0'..=b'9')) {
+            return Err(ScanError::new(start, "a number"));
+        }
+
+        self.digits();
+
+        Ok(())
+    }
+
+    fn digits(&mut self) {
+        while let Some(b'0'..=b'9') = self.peek() {
+            self.pos += 1;
+        }
+    }
+
+    fn literal(&mut self, literal: &'static str) -> Result<(), ScanError> {
+        if !self.text[self.pos..].starts_with(literal) {
+            return Err(ScanError::new(self.pos, literal));
+        }
+
+        self.pos += literal.len();
+
+        Ok(())
+    }
+
+    fn expect(&mut self, byte: u8, what: &'static str) -> Result<(), ScanError> {
+        if self.peek() != Some(byte) {
+            return Err(ScanError::new(self.pos, what));
+        }
+
+        self.pos += 1;
+
+        Ok(())
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(b' ' | b'\t' | b'\n' | b'\r') = self.peek() {
+            self.pos += 1;
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.text.as_bytes().get(self.pos).copied()
+    }
+}
+
+/// JSON that couldn't be scanned
+#[derive(Debug)]
+pub struct ScanError(String);
+
+impl ScanError {
+    fn new(pos: usize, expected: &str) -> Self {
+        Self(format!("expected {expected} at byte {pos}"))
+    }
+}
+
+impl Display for ScanError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for ScanError {}
+
+impl From<serde_json::Error> for ScanError {
+    fn from(err: serde_json::Error) -> Self {
+        Self(err.to_string())
+    }
+}
+
+/// Value to write, with the unchanged parts as they were read
+pub enum Preserved<'a> {
+    /// Unchanged, as it was written
+    Raw(&'a str),
+    /// Changed, or new
+    Value(&'a Value),
+    /// Changed object, some fields of which may be unchanged
+    Object(Vec<(Cow<'a, str>, Preserved<'a>)>),
+    /// Changed array, some items of which may be unchanged
+    Array(Vec<Preserved<'a>>),
+}
+
+impl Serialize for Preserved<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            // only checked here, once for the whole unchanged value rather than for every level inside it
+            Preserved::Raw(raw) => {
+                let raw: &RawValue = serde_json::from_str(raw).map_err(S::Error::custom)?;
+
+                raw.serialize(serializer)
+            }
+            Preserved::Value(value) => value.serialize(serializer),
+            Preserved::Object(fields) => {
+                let mut map = serializer.serialize_map(Some(fields.len()))?;
+
+                for (key, value) in fields {
+                    map.serialize_entry(key, value)?;
+                }
+
+                map.end()
+            }
+            Preserved::Array(items) => {
+                let mut seq = serializer.serialize_seq(Some(items.len()))?;
+
+                for item in items {
+                    seq.serialize_element(item)?;
+                }
+
+                seq.end()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `source` written back with `change` made to it, the way the saves are
+    fn rewrite(source: &str, change: impl FnOnce(&mut Value)) -> String {
+        let original: Value = serde_json::from_str(source).unwrap();
+        let mut value = original.clone();
+        change(&mut value);
+
+        let written = serde_json::to_vec_pretty(&preserving(&value, &original, source.as_bytes())).unwrap();
+
+        String::from_utf8(written).unwrap()
+    }
+
+    const SOURCE: &str = r#"{"money": 1e3, "day": 3, "list": ["b", "c", "a"], "nested": {"x": 1.50}}"#;
+
+    #[test]
+    fn unchanged_is_byte_identical() {
+        assert_eq!(rewrite(SOURCE, |_| {}), SOURCE);
+    }
+
+    #[test]
+    fn sorted_array_keeps_the_rest() {
+        let written = rewrite(SOURCE, |value| value["list"].as_array_mut().unwrap().sort_by_key(|v| v.to_string()));
+
+        assert_eq!(
+            written,
+            r#"{
+  "money": 1e3,
+  "day": 3,
+  "list": [
+    "a",
+    "b",
+    "c"
+  ],
+  "nested": {"x": 1.50}
+}"#
+        );
+    }
+
+    #[test]
+    fn added_key_goes_at_the_end() {
+        let written = rewrite(SOURCE, |value| value["added"] = Value::from(2));
+
+        assert_eq!(
+            written,
+            r#"{
+  "money": 1e3,
+  "day": 3,
+  "list": ["b", "c", "a"],
+  "nested": {"x": 1.50},
+  "added": 2
+}"#
+        );
+    }
+
+    #[test]
+    fn removed_key_keeps_the_rest() {
+        let written = rewrite(SOURCE, |value| {
+            value.as_object_mut().unwrap().remove("day");
+        });
+
+        assert_eq!(
+            written,
+            r#"{
+  "money": 1e3,
+  "list": ["b", "c", "a"],
+  "nested": {"x": 1.50}
+}"#
+        );
+    }
+
+    #[test]
+    fn numbers_follow_the_json_grammar() {
+        for valid in ["0", "-0", "12", "1.5", "-0.25e+3", "2E-2", "10e5"] {
+            assert!(scan(valid.as_bytes()).is_ok(), "{valid}");
+        }
+
+        for invalid in ["01", "1.", ".5", "-", "+1", "1e", "1e+", "1.e3", "--1", "0x10"] {
+            assert!(scan(invalid.as_bytes()).is_err(), "{invalid}");
+        }
+    }
+}
@@ -0,0 +1,110 @@
+use clap::{Args, Subcommand};
+use eyre::{Context, Result as EResult};
+use serde::Serialize;
+use std::fmt::Display;
+
+use crate::i18n::tr;
+use crate::known;
+use crate::output;
+use crate::save::{SaveFile, COSMETICS_LISTS};
+use crate::utils::SaveDirHandler;
+
+#[derive(Args)]
+#[derive(Debug)]
+pub struct Ops {
+    #[command(subcommand)]
+    action: Cmd,
+}
+
+#[derive(Subcommand)]
+#[derive(Debug)]
+enum Cmd {
+    /// Report how many of the known collectibles the save owns, and which are missing
+    Completion {
+        /// Save slot number (0-3)
+        save_slot: u8,
+    },
+}
+
+pub fn handler(ops: Ops, mut save_dir: SaveDirHandler) -> EResult<()> {
+    log::info!("Checking progress");
+
+    match ops.action {
+        Cmd::Completion { save_slot } => {
+            report_completion(&mut save_dir, save_slot).context("Failed to check completion")?
+        }
+    }
+
+    Ok(())
+}
+
+fn report_completion(save_dir: &mut SaveDirHandler, save_slot: u8) -> EResult<()> {
+    let save = SaveFile::open(save_dir, save_slot)?;
+    let save_data = save.typed_data()?;
+
+    let mut rows: Vec<Completion> = Vec::new();
+
+    for ((name, label), owned) in COSMETICS_LISTS.into_iter().zip(save_data.cosmetics()) {
+        rows.push(Completion::new(label, known::cosmetics(name), known::ITEMS_COMPLETE, |id| {
+            owned.iter().any(|o| o == id)
+        }));
+    }
+
+    let known_furniture: Vec<&str> = known::FURNITURE.iter().map(|(name, _)| *name).collect();
+
+    rows.push(Completion::new("Furniture", &known_furniture, known::ITEMS_COMPLETE, |id| {
+        save_data.furniture.iter().any(|f| f.name == *id)
+    }));
+
+    let known_emails: Vec<i64> = known::EMAILS.iter().map(|(id, _)| *id).collect();
+
+    // emails are only listed as they are found, there's no telling how many the game has
+    rows.push(Completion::new("Emails", &known_emails, false, |id| {
+        save_data.read_emails.contains(id) || save_data.unread_emails.contains(id)
+    }));
+
+    if rows.iter().any(|row| !row.complete) {
+        log::warn!("The Multitool doesn't know every item of the game yet, counts are only out of the known ones");
+    }
+
+    output::print(&rows, || rows.iter().for_each(Completion::print))
+}
+
+/// How much of the known things of one kind the save has
+#[derive(Serialize)]
+#[derive(Debug)]
+struct Completion {
+    label: &'static str,
+    have: usize,
+    total: usize,
+    /// Whether `total` is everything the game has, rather than only what the Multitool knows of
+    complete: bool,
+    missing: Vec<String>,
+}
+
+impl Completion {
+    fn new<T: Display>(label: &'static str, known: &[T], complete: bool, owned: impl Fn(&T) -> bool) -> Self {
+        let missing: Vec<String> = known.iter().filter(|id| !owned(id)).map(T::to_string).collect();
+        let have = known.len() - missing.len();
+
+        Self { label, have, total: known.len(), complete, missing }
+    }
+
+    fn print(&self) {
+        let Self { label, have, total, complete, missing } = self;
+        let label = tr!(&format!("category-{}", label.to_lowercase()));
+
+        // without the full list a percentage would claim more than is known
+        if !complete {
+            println!("{label:<10} {have:>4}/{total:<4}   -  {}", tr!("completion-incomplete"));
+        } else if *total == 0 {
+            println!("{label:<10} {have:>4}/{total:<4}   -");
+        } else {
+            println!("{label:<10} {have:>4}/{total:<4} {:>3.0}%", *have as f64 / *total as f64 * 100.0);
+        }
+
+        if !missing.is_empty() {
+            println!("    {}", tr!("completion-missing", items = missing.join(", ")));
+        }
+    }
+}
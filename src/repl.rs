@@ -0,0 +1,235 @@
+use clap::Args;
+use eyre::{Context, ContextCompat, Result as EResult};
+use serde_json::Value;
+use std::io::{self, Write};
+use tap::Tap;
+
+use crate::exit::Failure;
+use crate::i18n::tr;
+use crate::save::SaveFile;
+use crate::utils::{self, SaveDirHandler};
+
+#[derive(Args)]
+#[derive(Debug)]
+pub struct Ops {
+    /// Save slot number (0-3)
+    save_slot: u8,
+}
+
+pub fn handler(ops: Ops, mut save_dir: SaveDirHandler) -> EResult<()> {
+    log::info!("Starting REPL");
+
+    let mut save = SaveFile::open(&mut save_dir, ops.save_slot)?;
+    let mut repl = Repl { data: Value::Object(save.data()?.clone()), location: Vec::new(), changed: false };
+
+    eprintln!("{}", tr!("repl-intro"));
+
+    let mut lines = io::stdin().lines();
+
+    loop {
+        eprint!("{}> ", display_path(&repl.location));
+        io::stderr().flush().context("Failed to print the prompt")?;
+
+        let Some(line) = lines.next() else {
+            eprintln!();
+            break;
+        };
+        let line = line.context("Failed to read the command")?;
+
+        let result = match utils::split_words(&line) {
+            Ok(words) => match words.split_first() {
+                None => Ok(()),
+                Some((cmd, _)) if cmd == ":q" && repl.changed => {
+                    eprintln!("{}", tr!("repl-unsaved"));
+                    Ok(())
+                }
+                Some((cmd, _)) if cmd == ":q" || cmd == ":q!" => break,
+                Some((cmd, _)) if cmd == ":w" || cmd == ":wq" => {
+                    let result = repl.write(&mut save);
+
+                    if cmd == ":wq" && result.is_ok() {
+                        break;
+                    }
+
+                    result
+                }
+                Some((cmd, args)) => repl.run(cmd, args),
+            },
+            Err(err) => Err(err),
+        };
+
+        if let Err(err) = result {
+            eprintln!("{}: {err:#}", tr!("error"));
+        }
+    }
+
+    if repl.changed {
+        log::warn!("Quitting without writing the changes");
+    }
+
+    Ok(())
+}
+
+/// State of the interactive session
+struct Repl {
+    /// Save data, as it will be written
+    data: Value,
+    /// Path to the current location in the save data, from its root
+    location: Vec<String>,
+    /// Whether there are changes that weren't written yet
+    changed: bool,
+}
+
+impl Repl {
+    fn run(&mut self, cmd: &str, args: &[String]) -> EResult<()> {
+        match (cmd, args) {
+            ("help", []) => println!("{}", tr!("repl-help")),
+            ("ls", [] | [_]) => {
+                let path = self.resolve(args.first());
+
+                match self.get(&path)? {
+                    Value::Object(obj) => {
+                        for (key, val) in obj {
+                            println!("{key}\t{}", summary(val));
+                        }
+                    }
+                    Value::Array(arr) => {
+                        for (i, val) in arr.iter().enumerate() {
+                            println!("{i}\t{}", summary(val));
+                        }
+                    }
+                    val => println!("{val}"),
+                }
+            }
+            ("cd", [] | [_]) => {
+                let path = self.resolve(args.first());
+
+                match self.get(&path)? {
+                    Value::Object(_) | Value::Array(_) => self.location = path,
+                    _ => {
+                        let message = format!("{} is not an object or a list", display_path(&path));
+                        return Err(Failure::Invalid.error(message));
+                    }
+                }
+            }
+            ("get", [] | [_]) => {
+                let path = self.resolve(args.first());
+
+                println!("{}", serde_json::to_string_pretty(self.get(&path)?)?);
+            }
+            ("set", [key, value]) => {
+                let path = self.resolve(Some(key));
+                let current = self
+                    .data
+                    .pointer_mut(&pointer(&path))
+                    .ok_or_else(|| Failure::NotFound.details(format!("Nothing at {}", display_path(&path))))?;
+
+                // anything that isn't valid JSON is taken as a plain string
+                let new = serde_json::from_str::<Value>(value).unwrap_or_else(|_| Value::String(value.clone()));
+
+                log::info!("{}: changing {current} to {new}", display_path(&path));
+
+                *current = new;
+                self.changed = true;
+            }
+            ("find", [text]) => {
+                let mut found = Vec::new();
+                find(self.get(&self.location)?, self.location.clone(), text, &mut found);
+
+                for (path, val) in found {
+                    println!("{}\t{}", display_path(&path), summary(val));
+                }
+            }
+            _ => {
+                return Err(Failure::Invalid
+                    .details(format!("Unknown command or wrong arguments: {cmd}"))
+                    .suggestion("Type `help` for the list of commands")
+                    .into())
+            }
+        }
+
+        Ok(())
+    }
+
+    fn write(&mut self, save: &mut SaveFile) -> EResult<()> {
+        let data = self.data.as_object().context("Save data is not an object")?;
+        *save.data_mut()? = data.clone();
+
+        save.write()?;
+        self.changed = false;
+
+        log::info!("Saved changes");
+
+        Ok(())
+    }
+
+    /// Path to the `key` (like `a/b`, `../c` or `/d`) from the current location
+    fn resolve(&self, key: Option<&String>) -> Vec<String> {
+        let Some(key) = key else {
+            return self.location.clone();
+        };
+
+        let mut path = if key.starts_with('/') { Vec::new() } else { self.location.clone() };
+
+        for part in key.split('/') {
+            match part {
+                "" | "." => {}
+                ".." => {
+                    path.pop();
+                }
+                part => path.push(part.to_string()),
+            }
+        }
+
+        path
+    }
+
+    fn get(&self, path: &[String]) -> EResult<&Value> {
+        let val = self.data.pointer(&pointer(path)).ok_or_else(|| {
+            Failure::NotFound
+                .details(format!("Nothing at {}", display_path(path)))
+                .suggestion("Use `ls` to see what is there")
+        })?;
+
+        Ok(val)
+    }
+}
+
+/// Collect everything under `val` that has `text` in its key or (string) value
+fn find<'a>(val: &'a Value, path: Vec<String>, text: &str, found: &mut Vec<(Vec<String>, &'a Value)>) {
+    let children: Vec<(String, &Value)> = match val {
+        Value::Object(obj) => obj.iter().map(|(key, val)| (key.clone(), val)).collect(),
+        Value::Array(arr) => arr.iter().enumerate().map(|(i, val)| (i.to_string(), val)).collect(),
+        _ => return,
+    };
+
+    for (key, child) in children {
+        let path = path.clone().tap_mut(|p| p.push(key.clone()));
+
+        if key.contains(text) || child.as_str().is_some_and(|s| s.contains(text)) {
+            found.push((path.clone(), child));
+        }
+
+        find(child, path, text, found);
+    }
+}
+
+/// JSON pointer to the path
+pub(crate) fn pointer(path: &[String]) -> String {
+    path.iter()
+        .map(|part| format!("/{}", part.replace('~', "~0").replace('/', "~1")))
+        .collect()
+}
+
+pub(crate) fn display_path(path: &[String]) -> String {
+    format!("/{}", path.join("/"))
+}
+
+/// Short description of the value, for the listings
+fn summary(val: &Value) -> String {
+    match val {
+        Value::Object(obj) => format!("{{{} keys}}", obj.len()),
+        Value::Array(arr) => format!("[{} items]", arr.len()),
+        val => val.to_string(),
+    }
+}
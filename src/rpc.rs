@@ -0,0 +1,191 @@
+use eyre::{Context, Report, Result as EResult};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::io::{self, Write};
+
+use crate::utils::SaveDirHandler;
+use crate::{exit, organiser, outfits, save, serve};
+
+// Error codes from the JSON-RPC 2.0 spec
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+/// Method failed, with the error (as printed by `--error-format json`) in the `data`
+const FAILED: i64 = -32000;
+
+/// Answer JSON-RPC requests from stdin, one per line, until it's closed
+pub fn handler(mut save_dir: SaveDirHandler) -> EResult<()> {
+    log::info!("Waiting for JSON-RPC requests on stdin");
+
+    save::keep_parsed();
+
+    for line in io::stdin().lines() {
+        let line = line.context("Failed to read the request")?;
+
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        if let Some(response) = respond(&line, &mut save_dir) {
+            let mut stdout = io::stdout().lock();
+
+            serde_json::to_writer(&mut stdout, &response).context("Failed to write the response")?;
+            writeln!(stdout).and_then(|()| stdout.flush()).context("Failed to write the response")?;
+        }
+    }
+
+    log::info!("Input was closed, exiting");
+
+    Ok(())
+}
+
+/// Response to the request, or nothing if it's a notification
+fn respond(line: &str, save_dir: &mut SaveDirHandler) -> Option<Value> {
+    let request = match serde_json::from_str::<Value>(line) {
+        Ok(request) => request,
+        Err(err) => return Some(response(Value::Null, Err(error(PARSE_ERROR, err, None)))),
+    };
+
+    // requests without an id are notifications, which aren't answered
+    let id = request.get("id").cloned();
+
+    let Ok(Request { method, params }) = serde_json::from_value(request) else {
+        return Some(response(id.unwrap_or_default(), Err(error(INVALID_REQUEST, "Invalid request", None))));
+    };
+
+    log::info!("Calling {method}");
+
+    let result = call(&method, params, save_dir).map_err(|err| {
+        log::warn!("{method} failed: {}", err.1);
+
+        error(err.0, err.1, err.2)
+    });
+
+    id.map(|id| response(id, result))
+}
+
+fn call(method: &str, params: Value, save_dir: &mut SaveDirHandler) -> Result<Value, CallError> {
+    match method {
+        "slots" => Ok(json!(organiser::existing_slots(save_dir)?)),
+        "outfits.list" => Ok(json!(outfits::outfit_names(&serve::outfits_path(save_dir)?)?)),
+        "outfits.save" => {
+            let OutfitParams { slot, name, partial } = parse_params(params)?;
+            let outfits_path = serve::outfits_path(save_dir)?;
+
+            outfits::save_outfit(&outfits_path, name, save_dir, slot, partial).context("Failed to save the outfit")?;
+
+            Ok(Value::Null)
+        }
+        "outfits.load" => {
+            let OutfitParams { slot, name, partial } = parse_params(params)?;
+            let outfits_path = serve::outfits_path(save_dir)?;
+
+            outfits::load_outfit(&outfits_path, &name, save_dir, slot, partial).context("Failed to load the outfit")?;
+
+            Ok(Value::Null)
+        }
+        "values.get" => {
+            let ValueParams { slot, path } = parse_params(params)?;
+
+            Ok(serve::get_value(save_dir, slot, &split_path(&path))?)
+        }
+        "values.set" => {
+            let SetParams { slot, path, value } = parse_params(params)?;
+
+            serve::set_value(save_dir, slot, &split_path(&path), value)?;
+
+            Ok(Value::Null)
+        }
+        "organise" => {
+            let SlotParams { slot } = parse_params(params)?;
+
+            organiser::organise_slot(save_dir, slot).context("Failed to organise the save")?;
+
+            Ok(Value::Null)
+        }
+        _ => Err(CallError(METHOD_NOT_FOUND, format!("Method not found: {method}"), None)),
+    }
+}
+
+fn parse_params<T: DeserializeOwned>(params: Value) -> Result<T, CallError> {
+    serde_json::from_value(params).map_err(|err| CallError(INVALID_PARAMS, format!("Invalid params: {err}"), None))
+}
+
+/// Path like `hairlist/0` (same as in `repl`, but always from the root of the save data)
+fn split_path(path: &str) -> Vec<String> {
+    path.split('/').filter(|part| !part.is_empty()).map(String::from).collect()
+}
+
+fn response(id: Value, result: Result<Value, Value>) -> Value {
+    match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(error) => json!({ "jsonrpc": "2.0", "id": id, "error": error }),
+    }
+}
+
+fn error(code: i64, message: impl ToString, data: Option<Value>) -> Value {
+    let mut error = json!({ "code": code, "message": message.to_string() });
+
+    if let Some(data) = data {
+        error["data"] = data;
+    }
+
+    error
+}
+
+#[derive(Deserialize)]
+#[derive(Debug)]
+struct Request {
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+#[derive(Deserialize)]
+#[derive(Debug)]
+struct SlotParams {
+    slot: u8,
+}
+
+#[derive(Deserialize)]
+#[derive(Debug)]
+struct ValueParams {
+    slot: u8,
+    #[serde(default)]
+    path: String,
+}
+
+#[derive(Deserialize)]
+#[derive(Debug)]
+struct SetParams {
+    slot: u8,
+    path: String,
+    value: Value,
+}
+
+#[derive(Deserialize)]
+#[derive(Debug)]
+struct OutfitParams {
+    slot: u8,
+    name: String,
+    #[serde(default)]
+    partial: bool,
+}
+
+/// Code, message and data of the error to answer with
+#[derive(Debug)]
+struct CallError(i64, String, Option<Value>);
+
+impl From<Report> for CallError {
+    fn from(report: Report) -> Self {
+        CallError(FAILED, report.to_string(), Some(exit::to_json(&report)))
+    }
+}
+
+impl From<crate::Error> for CallError {
+    fn from(err: crate::Error) -> Self {
+        Report::from(err).into()
+    }
+}
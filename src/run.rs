@@ -0,0 +1,102 @@
+use clap::{Args, Parser};
+use eyre::{Context, Result as EResult};
+use std::io::{self, Read};
+use std::path::PathBuf;
+
+use crate::exit::Failure;
+use crate::save;
+use crate::timings;
+use crate::utils::{self, SaveDirHandler};
+use crate::vfs;
+use crate::cli::Command;
+
+#[derive(Args)]
+#[derive(Debug)]
+pub struct Ops {
+    /// File with the commands to run, one per line. If not specified (or `-`) - they are read from stdin
+    script_path: Option<PathBuf>,
+}
+
+pub fn handler(ops: Ops, save_dir: SaveDirHandler) -> EResult<()> {
+    // ======== Read input
+
+    let script = match ops.script_path {
+        Some(path) if path.as_os_str() != "-" => {
+            log::info!("Reading script {}", path.display());
+
+            let script = vfs::current().read(&path).context("Failed to read the script")?;
+            String::from_utf8(script).context("Script is not valid UTF-8")?
+        }
+        _ => {
+            log::info!("Reading script from stdin");
+
+            let mut script = String::new();
+            io::stdin().read_to_string(&mut script).context("Failed to read the script")?;
+            script
+        }
+    };
+
+    let mut steps: Vec<(usize, &str, Command)> = Vec::new();
+
+    for (i, line) in script.lines().enumerate().map(|(i, l)| (i + 1, l.trim())) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let action = parse_line(line).with_context(|| format!("Invalid command on line {i}: {line}"))?;
+        steps.push((i, line, action));
+    }
+
+    // ======== Run
+
+    save::keep_parsed();
+    utils::begin_transaction();
+
+    for (i, line, action) in steps {
+        log::info!("Line {i}: {line}");
+        let _timer = timings::stage(format!("Line {i}"));
+
+        if let Err(err) = crate::cli::dispatch(action, save_dir.clone()) {
+            utils::rollback_transaction();
+            log::warn!("Nothing was written");
+
+            return Err(err).with_context(|| format!("Failed on line {i}: {line}"));
+        }
+    }
+
+    log::info!("Every command succeeded, writing changes");
+
+    let _timer = timings::stage("Commit");
+    utils::commit_transaction().context("Failed to write changes")?;
+
+    log::info!("Finished running the script");
+
+    Ok(())
+}
+
+fn parse_line(line: &str) -> EResult<Command> {
+    let line = Line::try_parse_from(utils::split_words(line)?).map_err(|e| Failure::Invalid.error(e.render()))?;
+
+    match line.action {
+        Command::Run(_)
+        | Command::Repl(_)
+        | Command::Serve(_)
+        | Command::Open(_)
+        | Command::SelfUpdate(_)
+        | Command::Convert(_)
+        | Command::Completions(_)
+        | Command::Complete(_) => {
+            Err(Failure::Invalid.error("This command can't be used in a script"))
+        }
+        action => Ok(action),
+    }
+}
+
+/// Single line of the script
+#[derive(Parser)]
+#[derive(Debug)]
+#[command(no_binary_name = true, name = "run")]
+struct Line {
+    #[command(subcommand)]
+    action: Command,
+}
@@ -0,0 +1,404 @@
+use eyre::Context;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::SystemTime;
+
+use crate::error::{Error, Result};
+use crate::exit::Failure;
+use crate::preserve;
+use crate::timings;
+use crate::utils::{self, JObj, SaveDirHandler};
+use crate::vfs;
+
+/// Lists of owned wardrobe items, along with their labels
+pub const COSMETICS_LISTS: [(&str, &str); 5] = [
+    ("hairlist", "Hair"),
+    ("facelist", "Face"),
+    ("jewllist", "Accessory"),
+    ("shirtlist", "Shirt"),
+    ("jacketlist", "Jacket"),
+];
+
+/// Saves kept parsed after they were read or written, by their path. `None` unless enabled with [`keep_parsed`]
+static PARSED: Mutex<Option<HashMap<PathBuf, Parsed>>> = Mutex::new(None);
+
+/// Keep every save in memory once it's read, and reuse it instead of reading it again for as long as its file
+/// isn't changed
+///
+/// For running many commands on the same saves, where parsing a big save for each of them adds up
+pub fn keep_parsed() {
+    parsed().get_or_insert_with(HashMap::new);
+}
+
+/// Drop the saves kept by [`keep_parsed`], they are read from their files again the next time
+pub fn forget_parsed() {
+    if let Some(parsed) = parsed().as_mut() {
+        parsed.clear();
+    }
+}
+
+/// Stop keeping the save, after it was changed without being read in full
+fn forget(path: &Path) {
+    if let Some(parsed) = parsed().as_mut() {
+        parsed.remove(path);
+    }
+}
+
+fn parsed() -> MutexGuard<'static, Option<HashMap<PathBuf, Parsed>>> {
+    PARSED.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// When the file was changed, if the saves are kept parsed and it's known
+fn modified(path: &Path) -> Option<SystemTime> {
+    if parsed().is_none() {
+        return None;
+    }
+
+    vfs::current().modified(path).ok()
+}
+
+/// Contents of a save slot, read into memory
+pub struct SaveFile {
+    pub path: PathBuf,
+    json: Value,
+    /// Contents of the file as it was read, to write the unchanged parts back the same
+    source: Vec<u8>,
+    /// What was read from `source`, to tell which parts of it were changed
+    original: Arc<Value>,
+}
+
+impl SaveFile {
+    /// Read the save in the slot (0-3) of the save directory
+    pub fn open(save_dir: &mut SaveDirHandler, slot: u8) -> Result<Self> {
+        Self::open_path(slot_path(save_dir, slot)?)
+    }
+
+    /// Read a save file from anywhere, such as one picked by the user or a copy of a slot
+    pub fn open_path(path: PathBuf) -> Result<Self> {
+        // taken before reading, so that a change made while reading isn't missed
+        let modified = modified(&path);
+
+        if let Some(save) = modified.and_then(|modified| Self::kept(&path, modified)) {
+            return Ok(save);
+        }
+
+        log::info!("Reading save file {}", path.display());
+
+        let source = utils::read_file(&path).context("Failed to open save file")?;
+        let json = utils::parse_json(&path, &source).map_err(|err| match err.downcast_ref::<serde_json::Error>() {
+            Some(err) => invalid(&path, err),
+            None => err.into(),
+        })?;
+
+        if !json.is_object() {
+            return Err(invalid(&path, "not a JSON object"));
+        }
+
+        let original = Arc::new(json.clone());
+        let save = Self { path, json, source, original };
+        save.keep(&save.source, modified);
+
+        Ok(save)
+    }
+
+    /// Save kept parsed, if its file is still the same
+    fn kept(path: &Path, modified: SystemTime) -> Option<Self> {
+        let parsed = parsed();
+        let kept = parsed.as_ref()?.get(path).filter(|kept| kept.modified == modified)?;
+
+        log::info!("Save file {} didn't change since it was read, reusing it", path.display());
+
+        Some(Self {
+            path: path.to_owned(),
+            json: Value::clone(&kept.json),
+            source: kept.source.clone(),
+            original: kept.json.clone(),
+        })
+    }
+
+    /// Keep the save parsed, as it is in the file that was last changed at `modified`
+    fn keep(&self, source: &[u8], modified: Option<SystemTime>) {
+        let mut parsed = parsed();
+        let Some(parsed) = parsed.as_mut() else {
+            return;
+        };
+
+        match modified {
+            Some(modified) => {
+                let kept = Parsed { modified, json: Arc::new(self.json.clone()), source: source.to_vec() };
+                parsed.insert(self.path.clone(), kept);
+            }
+            None => {
+                parsed.remove(&self.path);
+            }
+        }
+    }
+
+    /// Contents of the save, the object the game keeps all of its state in
+    pub fn data(&self) -> Result<&JObj> {
+        self.json
+            .as_object()
+            .and_then(|json| json.get(utils::SAVE_DATA_KEY))
+            .and_then(Value::as_object)
+            .ok_or_else(|| invalid(&self.path, "save data is missing"))
+    }
+
+    /// Same as [`SaveFile::data`], for changing the save before [writing](SaveFile::write) it
+    pub fn data_mut(&mut self) -> Result<&mut JObj> {
+        self.json
+            .as_object_mut()
+            .and_then(|json| json.get_mut(utils::SAVE_DATA_KEY))
+            .and_then(Value::as_object_mut)
+            .ok_or_else(|| invalid(&self.path, "save data is missing"))
+    }
+
+    /// Known parts of the save, checked to be what the game writes
+    ///
+    /// Changes to it only end up in the save once given back with [`SaveFile::set_typed_data`]
+    pub fn typed_data(&self) -> Result<SaveData> {
+        serde_json::from_value(Value::Object(self.data()?.clone())).map_err(|err| invalid(&self.path, err))
+    }
+
+    pub fn set_typed_data(&mut self, data: SaveData) -> Result<()> {
+        let Value::Object(data) = serde_json::to_value(data).context("Failed to serialize save data")? else {
+            unreachable!("Save data is always serialized into an object")
+        };
+
+        *self.data_mut()? = data;
+
+        Ok(())
+    }
+
+    /// Write the save back to its slot, keeping the original as `.bak`
+    ///
+    /// Everything that wasn't changed is written exactly as it was read
+    pub fn write(&self) -> Result<()> {
+        let source = utils::serialize(&self.path, &preserve::preserving(&self.json, &self.original, &self.source))?;
+        utils::replace_file(&self.path, source.clone())?;
+
+        self.keep(&source, modified(&self.path));
+
+        Ok(())
+    }
+}
+
+/// Only some of the keys of the save data, read without parsing the rest of the save into values
+///
+/// Much faster than [`SaveFile`] for the commands that only need a few small keys of a big save, like the outfits
+pub struct PartialSave {
+    pub path: PathBuf,
+    /// Keys that were asked for, those of them the save has
+    data: JObj,
+    source: Vec<u8>,
+    /// Same keys as they were read, to tell which of them were changed
+    original: JObj,
+}
+
+impl PartialSave {
+    /// Read the keys of the save in the slot (0-3) of the save directory
+    pub fn open(save_dir: &mut SaveDirHandler, slot: u8, keys: &[&str]) -> Result<Self> {
+        Self::open_path(slot_path(save_dir, slot)?, keys)
+    }
+
+    pub fn open_path(path: PathBuf, keys: &[&str]) -> Result<Self> {
+        log::info!("Reading part of save file {}", path.display());
+        log::debug!("Reading keys {}", keys.join(", "));
+
+        let source = utils::read_file(&path).context("Failed to open save file")?;
+
+        let data = {
+            let _timer = timings::stage("Parse (partial)");
+            preserve::extract(&source, utils::SAVE_DATA_KEY, keys).map_err(|err| invalid(&path, err))?
+        };
+
+        let Some(data) = data else {
+            return Err(invalid(&path, "save data is missing"));
+        };
+
+        Ok(Self { path, original: data.clone(), data, source })
+    }
+
+    /// Keys that were read, and that the save has
+    pub fn data(&self) -> &JObj {
+        &self.data
+    }
+
+    /// Same as [`PartialSave::data`], for changing them. Only these keys are written, everything else is left as is
+    pub fn data_mut(&mut self) -> &mut JObj {
+        &mut self.data
+    }
+
+    /// Keys read into a part of the save data, like [`Wardrobe`]
+    pub fn typed_data<T: DeserializeOwned>(&self) -> Result<T> {
+        serde_json::from_value(Value::Object(self.data.clone())).map_err(|err| invalid(&self.path, err))
+    }
+
+    pub fn set_typed_data(&mut self, data: impl Serialize) -> Result<()> {
+        let Value::Object(data) = serde_json::to_value(data).context("Failed to serialize save data")? else {
+            return Err(invalid(&self.path, "part of the save data isn't an object"));
+        };
+
+        self.data.extend(data);
+
+        Ok(())
+    }
+
+    /// Write the save back to its slot with the keys changed, keeping the original as `.bak`
+    pub fn write(&self) -> Result<()> {
+        let patched = preserve::patching(&self.source, utils::SAVE_DATA_KEY, &self.data, &self.original)
+            .map_err(|err| invalid(&self.path, err))?;
+
+        utils::replace_file(&self.path, utils::serialize(&self.path, &patched)?)?;
+
+        // the whole save could be kept parsed from before, and wouldn't see this change inside a transaction
+        forget(&self.path);
+
+        Ok(())
+    }
+}
+
+/// Path of the save in the slot, which has to exist
+fn slot_path(save_dir: &mut SaveDirHandler, slot: u8) -> Result<PathBuf> {
+    let path = save_dir.resolve_save_slot(slot)?;
+
+    if !utils::file_exists(&path) {
+        return Err(Failure::SaveNotFound
+            .details(format!("Save slot {slot} is empty"))
+            .key(path.display())
+            .suggestion("Check the slot number, or specify the save directory with `--save-dir`")
+            .into());
+    }
+
+    Ok(path)
+}
+
+fn invalid(path: &Path, reason: impl Display) -> Error {
+    Error::InvalidSave { path: path.to_owned(), reason: reason.to_string() }
+}
+
+/// Save data, with the parts the Multitool works with the most read into their own fields
+///
+/// The rest of the keys are kept in [`SaveData::other`] as they are
+#[derive(Serialize, Deserialize)]
+#[derive(Debug)]
+pub struct SaveData {
+    #[serde(flatten)]
+    pub wardrobe: Wardrobe,
+    /// Owned furniture, both placed and in storage
+    #[serde(rename = "furnlist")]
+    pub furniture: Vec<FurnitureItem>,
+    /// IDs of read emails, newer first
+    #[serde(rename = "emailreadlist")]
+    pub read_emails: Vec<i64>,
+    /// IDs of unread emails, newer first
+    #[serde(rename = "emailunreadlist")]
+    pub unread_emails: Vec<i64>,
+    #[serde(flatten)]
+    pub other: JObj,
+}
+
+impl SaveData {
+    /// Same as [`Wardrobe::cosmetics`]
+    pub fn cosmetics(&self) -> [&[String]; 5] {
+        self.wardrobe.cosmetics()
+    }
+
+    pub fn cosmetics_mut(&mut self) -> [&mut Vec<String>; 5] {
+        self.wardrobe.cosmetics_mut()
+    }
+
+    /// Same as [`Wardrobe::worn`]
+    pub fn worn(&self) -> [&str; 5] {
+        self.wardrobe.worn()
+    }
+}
+
+/// Owned and worn wardrobe items, the part of the save data outfits work with
+#[derive(Serialize, Deserialize)]
+#[derive(Debug)]
+pub struct Wardrobe {
+    #[serde(rename = "hairlist")]
+    pub hair_owned: Vec<String>,
+    #[serde(rename = "facelist")]
+    pub face_owned: Vec<String>,
+    #[serde(rename = "jewllist")]
+    pub accessory_owned: Vec<String>,
+    #[serde(rename = "shirtlist")]
+    pub shirt_owned: Vec<String>,
+    #[serde(rename = "jacketlist")]
+    pub jacket_owned: Vec<String>,
+    #[serde(rename = "hairon")]
+    pub hair_worn: String,
+    #[serde(rename = "faceon")]
+    pub face_worn: String,
+    #[serde(rename = "jewlon")]
+    pub accessory_worn: String,
+    #[serde(rename = "shirton")]
+    pub shirt_worn: String,
+    #[serde(rename = "jacketon")]
+    pub jacket_worn: String,
+}
+
+impl Wardrobe {
+    /// Keys of the save data the wardrobe is read from, for [`PartialSave`]
+    pub const KEYS: [&'static str; 10] = [
+        "hairlist",
+        "facelist",
+        "jewllist",
+        "shirtlist",
+        "jacketlist",
+        "hairon",
+        "faceon",
+        "jewlon",
+        "shirton",
+        "jacketon",
+    ];
+
+    /// Owned wardrobe items, in the same order as [`COSMETICS_LISTS`]
+    pub fn cosmetics(&self) -> [&[String]; 5] {
+        [&self.hair_owned, &self.face_owned, &self.accessory_owned, &self.shirt_owned, &self.jacket_owned]
+    }
+
+    pub fn cosmetics_mut(&mut self) -> [&mut Vec<String>; 5] {
+        [
+            &mut self.hair_owned,
+            &mut self.face_owned,
+            &mut self.accessory_owned,
+            &mut self.shirt_owned,
+            &mut self.jacket_owned,
+        ]
+    }
+
+    /// Worn wardrobe items, in the same order as [`COSMETICS_LISTS`]
+    pub fn worn(&self) -> [&str; 5] {
+        [&self.hair_worn, &self.face_worn, &self.accessory_worn, &self.shirt_worn, &self.jacket_worn]
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[derive(Clone)]
+#[derive(Debug)]
+pub struct FurnitureItem {
+    pub name: String,
+    /// Anything else the game keeps for the item
+    #[serde(flatten)]
+    pub other: JObj,
+}
+
+impl FurnitureItem {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self { name: name.into(), other: JObj::new() }
+    }
+}
+
+/// Save kept by [`keep_parsed`], along with when its file was changed
+struct Parsed {
+    modified: SystemTime,
+    json: Arc<Value>,
+    source: Vec<u8>,
+}
@@ -0,0 +1,68 @@
+use clap::Args;
+use eyre::{eyre, Context, Result as EResult};
+use serde::Deserialize;
+use std::process;
+
+const RELEASES_URL: &str = "https://api.github.com/repos/NekoiNemo/hc_multitool/releases/latest";
+
+#[derive(Args)]
+#[derive(Debug)]
+pub struct Ops {}
+
+pub fn handler(_ops: Ops) -> EResult<()> {
+    // releases aren't signed yet, so a download couldn't be verified. Until they are installing is left to the user
+    log::info!("Checking for updates");
+
+    let current = env!("CARGO_PKG_VERSION");
+    let release: Release = serde_json::from_slice(&fetch(RELEASES_URL).context("Failed to get the latest release")?)
+        .context("Failed to parse the release info")?;
+    let latest = release.tag_name.trim_start_matches('v');
+
+    if parse_version(latest)? <= parse_version(current)? {
+        log::info!("Already on the latest version {current}");
+        return Ok(());
+    }
+
+    log::info!("New version {latest} is available (current is {current}), download it from {}", release.html_url);
+
+    Ok(())
+}
+
+/// Numbers of the version, like `[0, 3, 0]` for `0.3.0`
+///
+/// Pre-release versions (like `0.4.0-beta`) aren't handled and are refused as invalid, as the latest release is
+/// never one of them
+fn parse_version(version: &str) -> EResult<Vec<u64>> {
+    version
+        .split('.')
+        .map(|part| part.parse::<u64>())
+        .collect::<Result<_, _>>()
+        .with_context(|| format!("Invalid version {version}"))
+}
+
+/// Download the URL with `curl`, which comes with every supported system
+fn fetch(url: &str) -> EResult<Vec<u8>> {
+    log::debug!("Fetching {url}");
+
+    let output = process::Command::new("curl")
+        .args(["--fail", "--silent", "--show-error", "--location"])
+        .args(["--header", "Accept: application/vnd.github+json"])
+        .args(["--user-agent", concat!("hc_multitool/", env!("CARGO_PKG_VERSION"))])
+        .arg(url)
+        .output()
+        .context("Failed to run curl")?;
+
+    if !output.status.success() {
+        return Err(eyre!("{}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+
+    Ok(output.stdout)
+}
+
+#[derive(Deserialize)]
+#[derive(Debug)]
+struct Release {
+    tag_name: String,
+    /// Page of the release, with the files to download
+    html_url: String,
+}
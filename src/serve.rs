@@ -0,0 +1,405 @@
+use clap::Args;
+use eyre::{eyre, Context, Report, Result as EResult};
+use serde_json::{json, Value};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::exit::{self, Failure};
+use crate::outfits::{self, OUTFITS_FILE};
+use crate::save::{self, SaveFile};
+use crate::utils::{JObj, SaveDirHandler};
+use crate::{organiser, repl};
+
+/// Biggest request body accepted, well over the size of any save
+const MAX_BODY: usize = 64 * 1024 * 1024;
+/// How long a client has to send its request before it's dropped
+const TIMEOUT: Duration = Duration::from_secs(30);
+/// Longest request or header line accepted, in bytes
+const MAX_LINE: usize = 8 * 1024;
+/// Most headers accepted in one request
+const MAX_HEADERS: usize = 64;
+
+#[derive(Args)]
+#[derive(Debug)]
+pub struct Ops {
+    /// Address (and port) to listen on. Only local addresses are allowed
+    #[arg(long, default_value = "127.0.0.1:7878")]
+    listen: SocketAddr,
+    /// Token requests have to carry in `Authorization: Bearer <token>`. Random if not specified
+    #[arg(long)]
+    token: Option<String>,
+}
+
+pub fn handler(ops: Ops, mut save_dir: SaveDirHandler) -> EResult<()> {
+    if !ops.listen.ip().is_loopback() {
+        return Err(Failure::Invalid
+            .details(format!("Can't listen on {}, only local addresses are allowed", ops.listen))
+            .suggestion("Use 127.0.0.1 or [::1], e.g. `--listen 127.0.0.1:7878`")
+            .into());
+    }
+
+    let listener = TcpListener::bind(ops.listen).with_context(|| format!("Failed to listen on {}", ops.listen))?;
+    let address = listener.local_addr().context("Failed to get the address")?;
+    let token = match ops.token {
+        Some(token) => token,
+        None => random_token()?,
+    };
+    let access = Access { port: address.port(), token };
+
+    save::keep_parsed();
+
+    log::info!("Serving the API on http://{address}");
+    // printed rather than logged, so it's there even with logs turned down
+    println!("Token: {}", access.token);
+
+    // requests are handled one at a time, so two of them can never change the same save at once
+    for stream in listener.incoming() {
+        let result = stream
+            .context("Failed to accept a connection")
+            .and_then(|stream| serve_connection(stream, &access, &mut save_dir));
+
+        if let Err(err) = result {
+            log::warn!("{err:#}");
+        }
+    }
+
+    Ok(())
+}
+
+fn serve_connection(stream: TcpStream, access: &Access, save_dir: &mut SaveDirHandler) -> EResult<()> {
+    stream.set_read_timeout(Some(TIMEOUT)).context("Failed to set up the connection")?;
+
+    let mut reader = BufReader::new(&stream);
+
+    let response = match read_request(&mut reader) {
+        Ok(mut request) => {
+            log::info!("{} {}", request.method, request.target);
+
+            if let Err(response) = access.check(&request) {
+                log::warn!("{} {} rejected", request.method, request.target);
+
+                return response.write_to(&mut &stream).context("Failed to send the response");
+            }
+
+            // only read once the request is allowed, so nobody without the token can make it take up memory
+            let result = read_body(&mut reader, request.length).and_then(|body| {
+                request.body = body;
+                route(&request, save_dir)
+            });
+
+            match result {
+                Ok(body) => Response { status: if body.is_some() { 200 } else { 204 }, body },
+                Err(err) => {
+                    log::warn!("{} {} failed: {err:#}", request.method, request.target);
+
+                    Response::error(&err)
+                }
+            }
+        }
+        Err(err) => {
+            log::warn!("Failed to read the request: {err:#}");
+
+            Response::error(&err)
+        }
+    };
+
+    response.write_to(&mut &stream).context("Failed to send the response")
+}
+
+/// Request line and headers, leaving the body to [`read_body`]
+fn read_request(reader: &mut impl BufRead) -> EResult<Request> {
+    let line = read_line(reader).context("Failed to read the request line")?;
+
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return Err(Failure::Invalid.error("Malformed request line"));
+    };
+
+    let mut length = 0;
+    let mut host = None;
+    let mut authorization = None;
+
+    for count in 0.. {
+        let header = read_line(reader).context("Failed to read the headers")?;
+
+        if header.is_empty() {
+            break;
+        }
+        if count == MAX_HEADERS {
+            return Err(Failure::Invalid.error("Too many headers"));
+        }
+
+        if let Some((name, value)) = header.split_once(':') {
+            let value = value.trim();
+
+            if name.eq_ignore_ascii_case("content-length") {
+                length = value.parse().map_err(|_| Failure::Invalid.error("Invalid Content-Length"))?;
+            } else if name.eq_ignore_ascii_case("host") {
+                host = Some(value.to_string());
+            } else if name.eq_ignore_ascii_case("authorization") {
+                authorization = Some(value.to_string());
+            }
+        }
+    }
+
+    Ok(Request {
+        method: method.to_string(),
+        target: target.to_string(),
+        host,
+        authorization,
+        length,
+        body: Vec::new(),
+    })
+}
+
+fn read_body(reader: &mut impl Read, length: usize) -> EResult<Vec<u8>> {
+    if length > MAX_BODY {
+        return Err(Failure::Invalid.error("Request body is too large"));
+    }
+
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).context("Failed to read the request body")?;
+
+    Ok(body)
+}
+
+/// Line without its line break, refusing lines longer than [`MAX_LINE`]
+fn read_line(reader: &mut impl BufRead) -> EResult<String> {
+    let mut line = String::new();
+    reader.take(MAX_LINE as u64 + 1).read_line(&mut line)?;
+
+    if line.len() > MAX_LINE {
+        return Err(Failure::Invalid.error("Line is too long"));
+    }
+
+    Ok(line.trim_end_matches(['\r', '\n']).to_string())
+}
+
+/// Random token for the run, from the system's secure random source
+fn random_token() -> EResult<String> {
+    let mut bytes = [0; 16];
+    getrandom::getrandom(&mut bytes).map_err(|err| eyre!("Failed to generate the token: {err}"))?;
+
+    Ok(bytes.iter().map(|b| format!("{b:02x}")).collect())
+}
+
+/// Run the request, returning what to respond with (if anything)
+fn route(request: &Request, save_dir: &mut SaveDirHandler) -> EResult<Option<Value>> {
+    let (path, query) = request.target.split_once('?').unwrap_or((&request.target, ""));
+    let segments = path.split('/').filter(|s| !s.is_empty()).map(decode).collect::<EResult<Vec<_>>>()?;
+    let partial = query.split('&').any(|param| param == "partial" || param == "partial=true");
+
+    match (request.method.as_str(), segments.as_slice()) {
+        ("GET", [slots]) if slots == "slots" => Ok(Some(json!(organiser::existing_slots(save_dir)?))),
+        ("GET", [outfits]) if outfits == "outfits" => {
+            Ok(Some(json!(outfits::outfit_names(&outfits_path(save_dir)?)?)))
+        }
+        ("GET", [slots, slot, values, path @ ..]) if slots == "slots" && values == "values" => {
+            Ok(Some(get_value(save_dir, parse_slot(slot)?, path)?))
+        }
+        ("PUT", [slots, slot, values, path @ ..]) if slots == "slots" && values == "values" => {
+            let new = serde_json::from_slice::<Value>(&request.body)
+                .map_err(|err| Failure::Invalid.error(format!("Body is not valid JSON: {err}")))?;
+
+            set_value(save_dir, parse_slot(slot)?, path, new.clone())?;
+
+            Ok(Some(new))
+        }
+        ("POST", [slots, slot, outfits, name]) if slots == "slots" && outfits == "outfits" => {
+            let outfits_path = outfits_path(save_dir)?;
+
+            outfits::load_outfit(&outfits_path, name, save_dir, parse_slot(slot)?, partial)
+                .context("Failed to load the outfit")?;
+
+            Ok(None)
+        }
+        ("POST", [slots, slot, organise]) if slots == "slots" && organise == "organise" => {
+            organiser::organise_slot(save_dir, parse_slot(slot)?).context("Failed to organise the save")?;
+
+            Ok(None)
+        }
+        (method, _) => Err(Failure::NotFound
+            .details(format!("No such endpoint: {method} {path}"))
+            .suggestion("See the README for the list of endpoints")
+            .into()),
+    }
+}
+
+pub(crate) fn outfits_path(save_dir: &mut SaveDirHandler) -> EResult<PathBuf> {
+    save_dir.resolve_file(OUTFITS_FILE).context("Save dir not found")
+}
+
+fn parse_slot(slot: &str) -> EResult<u8> {
+    slot.parse().map_err(|_| Failure::Invalid.error(format!("Invalid save slot: {slot}")))
+}
+
+/// Value at the path inside the save data of the slot
+pub(crate) fn get_value(save_dir: &mut SaveDirHandler, slot: u8, path: &[String]) -> EResult<Value> {
+    let save = SaveFile::open(save_dir, slot)?;
+    let data = save.data()?;
+
+    match path.split_first() {
+        None => Ok(json!(data)),
+        Some((key, rest)) => data
+            .get(key)
+            .and_then(|val| val.pointer(&repl::pointer(rest)))
+            .cloned()
+            .ok_or_else(|| nothing_at(path)),
+    }
+}
+
+/// Replace the existing value at the path inside the save data of the slot, and write the save
+pub(crate) fn set_value(save_dir: &mut SaveDirHandler, slot: u8, path: &[String], new: Value) -> EResult<()> {
+    let mut save = SaveFile::open(save_dir, slot)?;
+    let current = value_mut(save.data_mut()?, path)?;
+
+    log::info!("{}: changing {current} to {new}", repl::display_path(path));
+
+    *current = new;
+    save.write()?;
+
+    Ok(())
+}
+
+/// Existing value at the path inside the save data. The path can't be empty, the save data as a whole can't be replaced
+fn value_mut<'a>(data: &'a mut JObj, path: &[String]) -> EResult<&'a mut Value> {
+    let Some((key, rest)) = path.split_first() else {
+        return Err(Failure::Invalid.error("Path to the value is missing"));
+    };
+
+    data.get_mut(key)
+        .and_then(|val| val.pointer_mut(&repl::pointer(rest)))
+        .ok_or_else(|| nothing_at(path))
+}
+
+fn nothing_at(path: &[String]) -> Report {
+    Failure::NotFound.error(format!("Nothing at {}", repl::display_path(path)))
+}
+
+/// Decode `%XX` escapes of a path segment
+fn decode(segment: &str) -> EResult<String> {
+    let mut bytes = Vec::with_capacity(segment.len());
+    let mut rest = segment.as_bytes();
+
+    while let Some((&byte, tail)) = rest.split_first() {
+        let escaped = (byte == b'%')
+            .then(|| tail.get(..2))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+
+        match escaped {
+            Some(decoded) => {
+                bytes.push(decoded);
+                rest = &tail[2..];
+            }
+            None => {
+                bytes.push(byte);
+                rest = tail;
+            }
+        }
+    }
+
+    String::from_utf8(bytes).map_err(|_| Failure::Invalid.error(format!("Path is not valid UTF-8: {segment}")))
+}
+
+/// What requests have to match to be served
+#[derive(Debug)]
+struct Access {
+    port: u16,
+    token: String,
+}
+
+impl Access {
+    /// Reject requests with any other `Host` than the local one (so web pages can't reach the API through DNS
+    /// rebinding), and the ones without the token
+    fn check(&self, request: &Request) -> Result<(), Response> {
+        let local = [
+            format!("127.0.0.1:{}", self.port),
+            format!("[::1]:{}", self.port),
+            format!("localhost:{}", self.port),
+        ];
+
+        if !request.host.as_ref().is_some_and(|host| local.contains(host)) {
+            return Err(Response::rejected(403, "Host is not allowed"));
+        }
+
+        let token = request.authorization.as_deref().and_then(|auth| auth.strip_prefix("Bearer "));
+
+        if !token.is_some_and(|token| same_secret(token.as_bytes(), self.token.as_bytes())) {
+            return Err(Response::rejected(401, "Missing or wrong token"));
+        }
+
+        Ok(())
+    }
+}
+
+/// Whether the secrets are the same, taking as long for any difference so the time doesn't tell how much matched
+fn same_secret(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+#[derive(Debug)]
+struct Request {
+    method: String,
+    /// Path, with the query if there is one
+    target: String,
+    host: Option<String>,
+    authorization: Option<String>,
+    /// Length of the body, from `Content-Length`
+    length: usize,
+    /// Empty until the request is allowed
+    body: Vec<u8>,
+}
+
+#[derive(Debug)]
+struct Response {
+    status: u16,
+    body: Option<Value>,
+}
+
+impl Response {
+    /// Error in the same JSON as `--error-format json` prints, with the status matching its kind
+    fn error(report: &Report) -> Self {
+        let status = match exit::kind(report) {
+            Some(Failure::SaveNotFound | Failure::NotFound) => 404,
+            Some(Failure::Invalid) => 400,
+            Some(Failure::WriteRefused) => 409,
+            None => 500,
+        };
+
+        Self { status, body: Some(exit::to_json(report)) }
+    }
+
+    /// Request that isn't allowed, in the same JSON as the other errors
+    fn rejected(status: u16, message: &str) -> Self {
+        Self { status, body: Some(exit::to_json(&eyre!("{message}"))) }
+    }
+
+    fn write_to(&self, stream: &mut impl Write) -> io::Result<()> {
+        let reason = match self.status {
+            200 => "OK",
+            204 => "No Content",
+            400 => "Bad Request",
+            401 => "Unauthorized",
+            403 => "Forbidden",
+            404 => "Not Found",
+            409 => "Conflict",
+            _ => "Internal Server Error",
+        };
+
+        write!(stream, "HTTP/1.1 {} {reason}\r\nConnection: close\r\n", self.status)?;
+
+        if let Some(body) = &self.body {
+            let body = serde_json::to_vec_pretty(body)?;
+
+            write!(stream, "Content-Type: application/json\r\nContent-Length: {}\r\n\r\n", body.len())?;
+            stream.write_all(&body)?;
+        } else {
+            write!(stream, "\r\n")?;
+        }
+
+        stream.flush()
+    }
+}
@@ -0,0 +1,99 @@
+use clap::{Args, Subcommand};
+use eyre::{Context, Result as EResult};
+use serde_json::Value;
+use std::path::PathBuf;
+
+use crate::exit::Failure;
+use crate::output;
+use crate::utils;
+
+#[derive(Args)]
+#[derive(Debug)]
+pub struct Ops {
+    /// Game settings file path
+    ///
+    /// Where the game keeps its settings isn't known yet, so there is no default
+    #[arg(long)]
+    settings_path: PathBuf,
+
+    /// Change a setting. If not specified - all settings are printed
+    #[command(subcommand)]
+    action: Option<Cmd>,
+}
+
+#[derive(Subcommand)]
+#[derive(Debug)]
+enum Cmd {
+    /// Change a setting
+    Set {
+        /// Name of the setting, with `.` separating nested ones (as printed)
+        setting: String,
+        /// New value, read as the same type as the current one
+        #[arg(allow_hyphen_values = true)]
+        value: String,
+    },
+}
+
+pub fn handler(ops: Ops) -> EResult<()> {
+    log::info!("Working with game settings");
+
+    let settings_file = ops.settings_path;
+
+    log::info!("Using settings file: {}", settings_file.display());
+
+    let mut settings = utils::read_json_file(&settings_file).context("Failed to read settings file")?;
+
+    let Some(Cmd::Set { setting, value }) = ops.action else {
+        return output::print(&settings, || print_settings("", &settings));
+    };
+
+    let pointer = format!("/{}", setting.replace('.', "/"));
+    let current = settings
+        .pointer_mut(&pointer)
+        .ok_or_else(|| {
+            Failure::NotFound
+                .details(format!("Setting {setting} not found"))
+                .key(&setting)
+                .suggestion("Use `settings` without a subcommand to see all of them")
+        })?;
+
+    let Some(new) = parse_as(current, &value) else {
+        return Err(Failure::Invalid.error(format!(
+            "Setting {setting} holds {current}, which can't be replaced with {value}"
+        )));
+    };
+
+    log::info!("{setting}: changing {current} to {new}");
+
+    *current = new;
+
+    utils::replace_json_file(&settings_file, &settings).context("Failed to write settings file")?;
+
+    log::info!("Saved settings");
+
+    Ok(())
+}
+
+/// Value of the same type as the current one, read from the text. Strings take the text as is, so `true` or `1`
+/// stay strings
+fn parse_as(current: &Value, value: &str) -> Option<Value> {
+    match current {
+        Value::String(_) => Some(Value::String(value.to_string())),
+        Value::Bool(_) => value.parse().ok().map(Value::Bool),
+        Value::Number(_) => serde_json::from_str::<Value>(value).ok().filter(Value::is_number),
+        Value::Null | Value::Array(_) | Value::Object(_) => None,
+    }
+}
+
+fn print_settings(prefix: &str, value: &Value) {
+    match value {
+        Value::Object(obj) => {
+            for (key, value) in obj {
+                let name = if prefix.is_empty() { key.clone() } else { format!("{prefix}.{key}") };
+
+                print_settings(&name, value);
+            }
+        }
+        _ => println!("{prefix}\t{value}"),
+    }
+}
@@ -0,0 +1,85 @@
+//! Measuring how long each stage of the work took, reported with the global `--timings`
+
+use std::sync::{Mutex, MutexGuard, OnceLock, PoisonError};
+use std::time::{Duration, Instant};
+
+static ENABLED: OnceLock<bool> = OnceLock::new();
+static TIMINGS: Mutex<Timings> = Mutex::new(Timings { stages: Vec::new(), depth: 0 });
+
+struct Timings {
+    stages: Vec<Stage>,
+    /// How many stages are currently running, for nesting the report
+    depth: usize,
+}
+
+#[cfg_attr(not(feature = "cli"), allow(dead_code))]
+struct Stage {
+    name: String,
+    depth: usize,
+    /// `None` while it's still running
+    took: Option<Duration>,
+}
+
+#[cfg(feature = "cli")]
+pub fn enable(enabled: bool) {
+    if ENABLED.set(enabled).is_err() {
+        log::warn!("Timings were already set up");
+    }
+}
+
+fn timings() -> MutexGuard<'static, Timings> {
+    TIMINGS.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Start measuring a stage, which lasts until the returned timer is dropped
+///
+/// Does nothing unless timings were enabled
+pub fn stage(name: impl Into<String>) -> Option<Timer> {
+    if !ENABLED.get().copied().unwrap_or_default() {
+        return None;
+    }
+
+    let mut timings = timings();
+    let depth = timings.depth;
+
+    timings.stages.push(Stage { name: name.into(), depth, took: None });
+    timings.depth += 1;
+
+    Some(Timer { index: timings.stages.len() - 1, start: Instant::now() })
+}
+
+pub struct Timer {
+    index: usize,
+    start: Instant,
+}
+
+impl Drop for Timer {
+    fn drop(&mut self) {
+        let took = self.start.elapsed();
+        let mut timings = timings();
+
+        timings.stages[self.index].took = Some(took);
+        timings.depth -= 1;
+    }
+}
+
+/// Print how long each of the stages took, if timings were enabled
+#[cfg(feature = "cli")]
+pub fn report() {
+    let timings = timings();
+
+    if timings.stages.is_empty() {
+        return;
+    }
+
+    eprintln!("Timings:");
+
+    for Stage { name, depth, took } in &timings.stages {
+        let name = format!("{}{name}", "  ".repeat(*depth));
+
+        match took {
+            Some(took) => eprintln!("  {name:<40} {:>10.2} ms", took.as_secs_f64() * 1000.0),
+            None => eprintln!("  {name:<40} {:>13}", "unfinished"),
+        }
+    }
+}
@@ -1,10 +1,19 @@
-use eyre::{eyre, Context, ContextCompat, Result as EResult};
+use eyre::{Context, ContextCompat, Result as EResult};
+use serde::Serialize;
 use serde_json::{Map, Value};
-use std::fs::File;
-use std::io::BufReader;
+use std::collections::HashMap;
+use std::io::{self, IsTerminal, Write};
 use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard, OnceLock, PoisonError};
 use tap::{Pipe, Tap};
 
+use crate::error::Result;
+use crate::exit::Failure;
+use crate::i18n::tr;
+use crate::save;
+use crate::timings;
+use crate::vfs;
+
 pub const SAVE_DATA_KEY: &str = "save_data_key";
 
 pub fn with_added_extension(path: &Path, ext: &str) -> PathBuf {
@@ -17,47 +26,408 @@ pub fn with_added_extension(path: &Path, ext: &str) -> PathBuf {
 }
 
 pub fn read_json_file(path: &Path) -> EResult<Value> {
+    let data = read_file(path)?;
+
+    parse_json(path, &data)
+}
+
+/// Contents of the file, counting the changes of the open transaction
+pub fn read_file(path: &Path) -> EResult<Vec<u8>> {
+    if let Some(transaction) = transaction().as_mut() {
+        match transaction.get(path) {
+            Some(Staged::Read(data) | Staged::Write { data, .. }) => return Ok(data.clone()),
+            Some(Staged::Remove) => {
+                return Err(Failure::NotFound.error(format!("File {} was removed earlier", path.display())))
+            }
+            None => {
+                let data = read_file_now(path)?;
+                transaction.insert(path.to_owned(), Staged::Read(data.clone()));
+
+                return Ok(data);
+            }
+        }
+    }
+
+    read_file_now(path)
+}
+
+fn read_file_now(path: &Path) -> EResult<Vec<u8>> {
     log::debug!("Reading file {}", path.display());
 
-    let file = File::open(path).with_context(|| format!("Failed to open file {}", path.display()))?;
+    let _timer = timings::stage(format!("Read {}", file_name(path)));
 
+    vfs::current().read(path).with_context(|| format!("Failed to open file {}", path.display()))
+}
+
+/// Parse contents of the file as JSON
+pub fn parse_json(path: &Path, data: &[u8]) -> EResult<Value> {
     log::debug!("Parsing file as JSON");
 
-    let json: Value = serde_json::from_reader(BufReader::new(file)).context("Failed to parse JSON in file")?;
+    let json: Value = {
+        let _timer = timings::stage(format!("Parse {}", file_name(path)));
+        serde_json::from_slice(data).context("Failed to parse JSON in file")?
+    };
 
     log::debug!("File was valid JSON");
 
     Ok(json)
 }
 
+pub fn write_json_file(path: &Path, value: &impl Serialize) -> EResult<()> {
+    write_file(path, serialize(path, value)?)
+}
+
+/// Same as [`write_json_file`], for the data that is already serialized
+pub fn write_file(path: &Path, data: Vec<u8>) -> EResult<()> {
+    if let Some(transaction) = transaction().as_mut() {
+        // keep the backup if the file was already replaced earlier in the transaction
+        let backup = matches!(transaction.get(path), Some(Staged::Write { backup: true, .. }));
+
+        log::debug!("Staging file {}", path.display());
+        transaction.insert(path.to_owned(), Staged::Write { data, backup });
+
+        return Ok(());
+    }
+
+    write_file_now(path, &data)
+}
+
+fn write_file_now(path: &Path, data: &[u8]) -> EResult<()> {
+    log::debug!("Writing file {}", path.display());
+
+    let _timer = timings::stage(format!("Write {}", file_name(path)));
+    vfs::current().write_atomic(path, data).with_context(|| format!("Failed to create file {}", path.display()))?;
+
+    Ok(())
+}
+
+/// Replace contents of the file, keeping the original as `.bak`
+pub fn replace_json_file(path: &Path, value: &impl Serialize) -> EResult<()> {
+    replace_file(path, serialize(path, value)?)
+}
+
+/// Same as [`replace_json_file`], for the data that is already serialized
+pub fn replace_file(path: &Path, data: Vec<u8>) -> EResult<()> {
+    if let Some(transaction) = transaction().as_mut() {
+        log::debug!("Staging file {}", path.display());
+        transaction.insert(path.to_owned(), Staged::Write { data, backup: true });
+
+        return Ok(());
+    }
+
+    replace_file_now(path, &data)
+}
+
+fn replace_file_now(path: &Path, data: &[u8]) -> EResult<()> {
+    let _timer = timings::stage(format!("Write {}", file_name(path)));
+
+    let fs = vfs::current();
+    let output_tmp = with_added_extension(path, "new");
+    fs.write_atomic(&output_tmp, data).context("Failed to write output file")?;
+
+    fs.rename(path, &with_added_extension(path, "bak")).context("Failed to make backup of the original file")?;
+    fs.rename(&output_tmp, path).context("Failed to rename output file to replace input")?;
+
+    Ok(())
+}
+
+pub fn serialize(path: &Path, value: &impl Serialize) -> EResult<Vec<u8>> {
+    let _timer = timings::stage(format!("Serialize {}", file_name(path)));
+
+    serde_json::to_vec_pretty(value).context("Failed to serialize JSON")
+}
+
+/// Name of the file, for the timings
+fn file_name(path: &Path) -> String {
+    path.file_name().unwrap_or(path.as_os_str()).to_string_lossy().into_owned()
+}
+
+pub fn remove_file(path: &Path) -> EResult<()> {
+    if let Some(transaction) = transaction().as_mut() {
+        log::debug!("Staging removal of {}", path.display());
+        transaction.insert(path.to_owned(), Staged::Remove);
+
+        return Ok(());
+    }
+
+    remove_file_now(path)
+}
+
+fn remove_file_now(path: &Path) -> EResult<()> {
+    vfs::current().remove(path).with_context(|| format!("Failed to remove file {}", path.display()))
+}
+
+/// Whether the file exists, counting the changes of the open transaction
+pub fn file_exists(path: &Path) -> bool {
+    match transaction().as_ref().and_then(|t| t.get(path)) {
+        Some(Staged::Read(_) | Staged::Write { .. }) => true,
+        Some(Staged::Remove) => false,
+        None => vfs::current().exists(path),
+    }
+}
+
+/// Files touched while a transaction is open, by their path
+///
+/// While it's open nothing is written to the disk, and each file is only read once
+static TRANSACTION: Mutex<Option<HashMap<PathBuf, Staged>>> = Mutex::new(None);
+
+enum Staged {
+    /// Read, but not changed
+    Read(Vec<u8>),
+    /// Changed, `backup` if the original should be kept as `.bak`
+    Write { data: Vec<u8>, backup: bool },
+    Remove,
+}
+
+fn transaction() -> MutexGuard<'static, Option<HashMap<PathBuf, Staged>>> {
+    TRANSACTION.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Start keeping every change to the files in memory, until [commit_transaction] or [rollback_transaction]
+pub fn begin_transaction() {
+    transaction().get_or_insert_with(HashMap::new);
+}
+
+/// Write every change made since [begin_transaction] to the disk, either all of them or none
+///
+/// New contents are written next to the files first, and only once all of them are there the files are swapped
+/// in. Originals are moved aside rather than overwritten or removed, so a failed swap can put them back
+pub fn commit_transaction() -> EResult<()> {
+    let Some(staged) = transaction().take() else {
+        return Ok(());
+    };
+
+    let changes: Vec<(PathBuf, Staged)> =
+        staged.into_iter().filter(|(_, change)| !matches!(change, Staged::Read(_))).collect();
+
+    // ======== Write the new contents aside
+
+    let mut written: Vec<PathBuf> = Vec::new();
+
+    for (path, change) in &changes {
+        if let Staged::Write { data, .. } = change {
+            let new_path = with_added_extension(path, "new");
+            let result = write_file_now(&new_path, data);
+
+            if let Err(err) = result {
+                written.iter().for_each(|p| discard(p));
+                return Err(err);
+            }
+
+            written.push(new_path);
+        }
+    }
+
+    // ======== Swap them in
+
+    let mut done: Vec<Swapped> = Vec::new();
+
+    for (path, change) in &changes {
+        if let Err(err) = swap_in(path, change, &mut done) {
+            log::warn!("Failed to write {}, putting back the changed files", path.display());
+
+            for swapped in done.into_iter().rev() {
+                swapped.undo();
+            }
+            written.iter().for_each(|p| discard(p));
+
+            return Err(err);
+        }
+    }
+
+    // ======== Tidy up the originals
+
+    for swapped in done {
+        swapped.finish();
+    }
+
+    Ok(())
+}
+
+/// Step of [commit_transaction] that can be undone
+enum Swapped {
+    /// Original was moved aside to `old`, `backup` if it should be kept as `.bak` afterwards
+    Moved { path: PathBuf, old: PathBuf, backup: bool },
+    /// File didn't exist before
+    Created(PathBuf),
+}
+
+impl Swapped {
+    fn undo(self) {
+        let fs = vfs::current();
+
+        let result = match &self {
+            Swapped::Moved { path, old, .. } => fs.rename(old, path),
+            Swapped::Created(path) => fs.remove(path),
+        };
+
+        if let Err(err) = result {
+            log::error!("Failed to undo the change: {err}");
+        }
+    }
+
+    fn finish(self) {
+        let fs = vfs::current();
+
+        // changes are already in place by now, a leftover original is only worth a warning
+        let result = match &self {
+            Swapped::Moved { path, old, backup: true } => fs.rename(old, &with_added_extension(path, "bak")),
+            Swapped::Moved { old, .. } => fs.remove(old),
+            Swapped::Created(_) => Ok(()),
+        };
+
+        if let Err(err) = result {
+            log::warn!("Failed to tidy up the original file: {err}");
+        }
+    }
+}
+
+fn swap_in(path: &Path, change: &Staged, done: &mut Vec<Swapped>) -> EResult<()> {
+    let fs = vfs::current();
+    let existed = fs.exists(path);
+
+    if existed {
+        let old = with_added_extension(path, "old");
+        fs.rename(path, &old).with_context(|| format!("Failed to move aside {}", path.display()))?;
+
+        let backup = matches!(change, Staged::Write { backup: true, .. });
+        done.push(Swapped::Moved { path: path.to_owned(), old, backup });
+    }
+
+    if let Staged::Write { .. } = change {
+        fs.rename(&with_added_extension(path, "new"), path)
+            .with_context(|| format!("Failed to replace {}", path.display()))?;
+
+        if !existed {
+            done.push(Swapped::Created(path.to_owned()));
+        }
+    }
+
+    Ok(())
+}
+
+/// Remove a file that was only written for a commit that didn't go through, unless it was already swapped in
+fn discard(path: &Path) {
+    let fs = vfs::current();
+
+    if !fs.exists(path) {
+        return;
+    }
+
+    if let Err(err) = fs.remove(path) {
+        log::warn!("Failed to remove {}: {err}", path.display());
+    }
+}
+
+/// Drop every change made since [begin_transaction]
+pub fn rollback_transaction() {
+    transaction().take();
+
+    // saves kept parsed may have the changes that were just dropped
+    save::forget_parsed();
+}
+
+/// Split the line into words on whitespace, keeping anything in quotes (`"` or `'`) together
+pub fn split_words(line: &str) -> EResult<Vec<String>> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut quote: Option<char> = None;
+
+    for c in line.chars() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), c) => word.get_or_insert_with(String::new).push(c),
+            (None, '"' | '\'') => {
+                quote = Some(c);
+                word.get_or_insert_with(String::new);
+            }
+            (None, c) if c.is_whitespace() => words.extend(word.take()),
+            (None, c) => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+
+    if quote.is_some() {
+        return Err(Failure::Invalid.error("Unclosed quote"));
+    }
+
+    words.extend(word);
+
+    Ok(words)
+}
+
+/// Answer "yes" to every confirmation, set with the global `--yes`
+static ASSUME_YES: OnceLock<bool> = OnceLock::new();
+
+pub fn set_assume_yes(yes: bool) {
+    if ASSUME_YES.set(yes).is_err() {
+        log::warn!("Confirmation mode was already set");
+    }
+}
+
+/// Ask user a yes/no question, defaulting to "no"
+///
+/// Only asks when attached to a terminal, otherwise (as well as with `--yes`) the answer is always "yes"
+pub fn confirm(question: &str) -> EResult<bool> {
+    if ASSUME_YES.get().copied().unwrap_or_default() {
+        log::debug!("{question} Assuming yes");
+        return Ok(true);
+    }
+
+    if !io::stdin().is_terminal() {
+        log::debug!("{question} Not a terminal, assuming yes");
+        return Ok(true);
+    }
+
+    eprint!("{question} {} ", tr!("confirm-options"));
+    io::stderr().flush().context("Failed to print the question")?;
+
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer).context("Failed to read the answer")?;
+
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+/// Game's save directory, located the first time it's needed
+#[derive(Clone)]
 pub struct SaveDirHandler {
     save_dir: Option<PathBuf>,
     dir_override: Option<PathBuf>,
 }
 
 impl SaveDirHandler {
+    /// Use `dir_override` as the save dir if set, instead of looking for it in the default location
     pub fn new_override(dir_override: Option<PathBuf>) -> Self {
         Self { save_dir: None, dir_override }
     }
     fn default_dir() -> EResult<PathBuf> {
         log::info!("Locating game save dir");
 
-        let mut dir = dirs::data_dir().context("Unable to determine system's data dir")?;
+        #[cfg(feature = "cli")]
+        let data_dir = dirs::data_dir();
+        // system dirs aren't looked up without the `cli` feature, so the save dir always has to be given
+        #[cfg(not(feature = "cli"))]
+        let data_dir: Option<PathBuf> = None;
+
+        let mut dir = data_dir.context("Unable to determine system's data dir")?;
         dir.push("godot/app_userdata/HARDCODED");
 
-        if dir.exists() && dir.is_dir() {
+        if vfs::current().is_dir(&dir) {
             Ok(dir)
         } else {
-            Err(eyre!(
-                "Path {} doesn't exist or is not a directory",
-                dir.display()
-            ))
+            Err(Failure::SaveNotFound
+                .details(format!("Path {} doesn't exist or is not a directory", dir.display()))
+                .key(dir.display())
+                .suggestion("Specify the save directory with `--save-dir`")
+                .into())
         }
     }
 
     fn resolve_save_dir(&self) -> EResult<PathBuf> {
         match self.dir_override.as_ref() {
-            Some(dir) if !dir.is_dir() => Err(eyre!("Override path {} isn't a directory", dir.display())),
+            Some(dir) if !vfs::current().is_dir(dir) => Err(Failure::SaveNotFound
+                .details(format!("Override path {} isn't a directory", dir.display()))
+                .key(dir.display())
+                .into()),
             Some(dir) => {
                 log::info!("Save dir overridden to {}", dir.display());
 
@@ -67,7 +437,13 @@ impl SaveDirHandler {
         }
     }
 
-    pub fn get_save_dir(&mut self) -> EResult<&Path> {
+    /// Whether the save dir was specified with `--save-dir`, rather than found automatically
+    pub fn is_overridden(&self) -> bool {
+        self.dir_override.is_some()
+    }
+
+    /// Path to the save dir, failing if it doesn't exist
+    pub fn get_save_dir(&mut self) -> Result<&Path> {
         if let Some(ref dir) = self.save_dir {
             return Ok(dir);
         }
@@ -80,9 +456,15 @@ impl SaveDirHandler {
         Ok(dir)
     }
 
-    pub fn resolve_save_slot(&mut self, slot: u8) -> EResult<PathBuf> {
+    /// Path to the file with this name in the save dir
+    pub fn resolve_file(&mut self, name: &str) -> Result<PathBuf> {
+        Ok(self.get_save_dir()?.join(name))
+    }
+
+    /// Path to the save file of the slot (0-3), which may not exist
+    pub fn resolve_save_slot(&mut self, slot: u8) -> Result<PathBuf> {
         if slot > 3 {
-            Err(eyre!("Invalid save slot {slot}, expected 0-3"))?
+            Err(Failure::Invalid.details(format!("Invalid save slot {slot}, expected 0-3")))?
         }
 
         self.get_save_dir()?
@@ -92,9 +474,11 @@ impl SaveDirHandler {
     }
 }
 
+/// JSON object, as the save data and most things inside it are
 pub type JObj = Map<String, Value>;
 pub type JArr = Vec<Value>;
 
+/// Getters for the fields of [`JObj`], failing with the name of the key when it's missing or of another type
 pub trait ObjExt {
     fn e_get(&self, name: &str) -> EResult<&Value>;
     fn e_get_mut(&mut self, name: &str) -> EResult<&mut Value>;
@@ -108,6 +492,8 @@ pub trait ObjExt {
     fn get_arr_mut(&mut self, name: &str) -> EResult<&mut JArr>;
 
     fn get_str(&self, name: &str) -> EResult<&str>;
+
+    fn get_str_list(&self, name: &str) -> EResult<Vec<String>>;
 }
 
 impl ObjExt for JObj {
@@ -150,4 +536,16 @@ impl ObjExt for JObj {
             .as_str()
             .with_context(|| format!("Key {name}: not a string"))
     }
+
+    fn get_str_list(&self, name: &str) -> EResult<Vec<String>> {
+        self.get_arr(name)?
+            .iter()
+            .map(|val| {
+                val.as_str()
+                    .with_context(|| format!("Expected a string, got: {val:#?}"))
+                    .map(String::from)
+            })
+            .collect::<EResult<Vec<String>>>()
+            .with_context(|| format!("Key {name}: failed to parse array element"))
+    }
 }
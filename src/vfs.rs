@@ -0,0 +1,289 @@
+//! Filesystem every file of the saves is read from and written to
+//!
+//! Defaults to the real one, but can be swapped for a [`MemoryFs`] to run the commands against files that only
+//! exist in memory, or to keep the changes there instead of writing them (which is what `--dry-run` does)
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError, RwLock};
+use std::time::SystemTime;
+
+use crate::utils;
+
+/// Operations on the files the Multitool needs
+pub trait Fs: Send + Sync {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>>;
+
+    /// Write the file, so that it's either fully written or left as it was
+    fn write_atomic(&self, path: &Path, data: &[u8]) -> io::Result<()>;
+
+    /// Add the data to the end of the file, creating it if there isn't one
+    fn append(&self, path: &Path, data: &[u8]) -> io::Result<()>;
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()>;
+
+    fn remove(&self, path: &Path) -> io::Result<()>;
+
+    /// Create the directory, along with the ones it's in
+    fn create_dir_all(&self, path: &Path) -> io::Result<()>;
+
+    /// Paths of the entries in the directory, in no particular order
+    fn list_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>>;
+
+    fn exists(&self, path: &Path) -> bool;
+
+    fn is_dir(&self, path: &Path) -> bool;
+
+    /// Size of the file, in bytes
+    fn size(&self, path: &Path) -> io::Result<u64>;
+
+    /// When the file was last changed
+    fn modified(&self, path: &Path) -> io::Result<SystemTime>;
+}
+
+static FS: RwLock<Option<Arc<dyn Fs>>> = RwLock::new(None);
+
+/// Filesystem currently in use
+pub fn current() -> Arc<dyn Fs> {
+    FS.read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone()
+        .unwrap_or_else(|| Arc::new(RealFs))
+}
+
+/// Use this filesystem for everything from now on
+pub fn set(fs: Arc<dyn Fs>) {
+    *FS.write().unwrap_or_else(PoisonError::into_inner) = Some(fs);
+}
+
+#[derive(Clone, Copy)]
+#[derive(Debug)]
+pub struct RealFs;
+
+impl Fs for RealFs {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        fs::read(path)
+    }
+
+    fn write_atomic(&self, path: &Path, data: &[u8]) -> io::Result<()> {
+        let tmp = utils::with_added_extension(path, "tmp");
+
+        let mut file = fs::File::create(&tmp)?;
+        file.write_all(data)?;
+        // otherwise a crash right after the rename can leave the file renamed, but still empty
+        file.sync_all()?;
+
+        fs::rename(&tmp, path)
+    }
+
+    fn append(&self, path: &Path, data: &[u8]) -> io::Result<()> {
+        fs::OpenOptions::new().create(true).append(true).open(path)?.write_all(data)
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        fs::rename(from, to)
+    }
+
+    fn remove(&self, path: &Path) -> io::Result<()> {
+        fs::remove_file(path)
+    }
+
+    fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+        fs::create_dir_all(path)
+    }
+
+    fn list_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+        fs::read_dir(path)?.map(|entry| Ok(entry?.path())).collect()
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        path.exists()
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        path.is_dir()
+    }
+
+    fn size(&self, path: &Path) -> io::Result<u64> {
+        Ok(fs::metadata(path)?.len())
+    }
+
+    fn modified(&self, path: &Path) -> io::Result<SystemTime> {
+        fs::metadata(path)?.modified()
+    }
+}
+
+/// Files kept in memory, optionally on top of another filesystem
+///
+/// With a base filesystem, files not touched yet are read from it, and every change stays in memory. Directories
+/// only exist as long as there are files in them
+pub struct MemoryFs {
+    base: Option<Box<dyn Fs>>,
+    /// Contents of the files, `None` for the ones removed from the base
+    files: Mutex<BTreeMap<PathBuf, Option<Vec<u8>>>>,
+}
+
+impl MemoryFs {
+    /// Filesystem with no files
+    pub fn new() -> Self {
+        Self { base: None, files: Mutex::new(BTreeMap::new()) }
+    }
+
+    /// Filesystem that reads from `base`, but keeps the changes to itself
+    pub fn over(base: impl Fs + 'static) -> Self {
+        Self { base: Some(Box::new(base)), files: Mutex::new(BTreeMap::new()) }
+    }
+
+    /// Add the file, for setting up the files before running anything
+    pub fn insert(&self, path: impl Into<PathBuf>, data: impl Into<Vec<u8>>) {
+        self.files().insert(path.into(), Some(data.into()));
+    }
+
+    /// Files that were written, or added with [`MemoryFs::insert`]
+    pub fn written(&self) -> Vec<PathBuf> {
+        self.files().iter().filter(|(_, data)| data.is_some()).map(|(path, _)| path.clone()).collect()
+    }
+
+    /// Files of the base that were removed or renamed
+    pub fn removed(&self) -> Vec<PathBuf> {
+        self.files().iter().filter(|(_, data)| data.is_none()).map(|(path, _)| path.clone()).collect()
+    }
+
+    fn files(&self) -> MutexGuard<'_, BTreeMap<PathBuf, Option<Vec<u8>>>> {
+        self.files.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn base_exists(&self, path: &Path) -> bool {
+        self.base.as_ref().is_some_and(|base| base.exists(path))
+    }
+
+    fn remove_entry(&self, files: &mut BTreeMap<PathBuf, Option<Vec<u8>>>, path: &Path) {
+        // removal of the base file has to be remembered, otherwise it would be read from the base again
+        if self.base_exists(path) {
+            files.insert(path.to_owned(), None);
+        } else {
+            files.remove(path);
+        }
+    }
+}
+
+impl Default for MemoryFs {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Fs for MemoryFs {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        let stored = self.files().get(path).cloned();
+
+        match (stored, &self.base) {
+            (Some(Some(data)), _) => Ok(data),
+            (None, Some(base)) => base.read(path),
+            _ => Err(not_found(path)),
+        }
+    }
+
+    fn write_atomic(&self, path: &Path, data: &[u8]) -> io::Result<()> {
+        log::debug!("Keeping {} in memory", path.display());
+
+        self.files().insert(path.to_owned(), Some(data.to_vec()));
+
+        Ok(())
+    }
+
+    fn append(&self, path: &Path, data: &[u8]) -> io::Result<()> {
+        let mut contents = if self.exists(path) { self.read(path)? } else { Vec::new() };
+        contents.extend_from_slice(data);
+
+        self.files().insert(path.to_owned(), Some(contents));
+
+        Ok(())
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        let data = self.read(from)?;
+        let mut files = self.files();
+
+        self.remove_entry(&mut files, from);
+        files.insert(to.to_owned(), Some(data));
+
+        Ok(())
+    }
+
+    fn remove(&self, path: &Path) -> io::Result<()> {
+        if !self.exists(path) {
+            return Err(not_found(path));
+        }
+
+        self.remove_entry(&mut self.files(), path);
+
+        Ok(())
+    }
+
+    fn create_dir_all(&self, _path: &Path) -> io::Result<()> {
+        // directories only exist as long as there are files in them
+        Ok(())
+    }
+
+    fn list_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+        let mut entries = match &self.base {
+            Some(base) if base.is_dir(path) => base.list_dir(path)?,
+            _ if self.is_dir(path) => Vec::new(),
+            _ => return Err(not_found(path)),
+        };
+        let files = self.files();
+
+        entries.retain(|entry| !matches!(files.get(entry), Some(None)));
+
+        for (file, data) in files.iter() {
+            // nested files make their directories show up as entries
+            let entry = file.strip_prefix(path).ok().and_then(|rest| rest.components().next());
+
+            if let (Some(entry), Some(_)) = (entry, data) {
+                let entry = path.join(entry);
+
+                if !entries.contains(&entry) {
+                    entries.push(entry);
+                }
+            }
+        }
+
+        Ok(entries)
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        let stored = self.files().get(path).map(Option::is_some);
+
+        stored.unwrap_or_else(|| self.base_exists(path) || self.is_dir(path))
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        let in_memory = self
+            .files()
+            .iter()
+            .any(|(file, data)| data.is_some() && file != path && file.starts_with(path));
+
+        in_memory || self.base.as_ref().is_some_and(|base| base.is_dir(path))
+    }
+
+    fn size(&self, path: &Path) -> io::Result<u64> {
+        self.read(path).map(|data| data.len() as u64)
+    }
+
+    fn modified(&self, path: &Path) -> io::Result<SystemTime> {
+        let stored = self.files().contains_key(path);
+
+        match (stored, &self.base) {
+            (false, Some(base)) => base.modified(path),
+            // files in memory aren't timed
+            _ => Err(io::Error::new(io::ErrorKind::Unsupported, format!("{} is kept in memory", path.display()))),
+        }
+    }
+}
+
+fn not_found(path: &Path) -> io::Error {
+    io::Error::new(io::ErrorKind::NotFound, format!("{} doesn't exist", path.display()))
+}
@@ -0,0 +1,232 @@
+use clap::{Args, Subcommand, ValueEnum};
+use eyre::{Context, Result as EResult};
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+use crate::exit::Failure;
+use crate::i18n::tr;
+use crate::known;
+use crate::save::{SaveFile, COSMETICS_LISTS};
+use crate::utils::{self, SaveDirHandler};
+
+#[derive(Args)]
+#[derive(Debug)]
+pub struct Ops {
+    #[command(subcommand)]
+    action: Cmd,
+}
+
+#[derive(Subcommand)]
+#[derive(Debug)]
+enum Cmd {
+    /// Export a checklist of every known wardrobe item, and whether the save owns it
+    Export {
+        /// Save slot number (0-3)
+        save_slot: u8,
+        /// Format of the checklist
+        ///
+        /// Not the global `--format`, which is for the output of the commands that list things
+        #[arg(short = 'f', long, default_value = "md")]
+        checklist_format: ExportFormat,
+        /// File to write the checklist to. If not specified - it's printed
+        #[arg(short, long)]
+        output_path: Option<PathBuf>,
+    },
+    /// Unlock wardrobe items listed in a text file
+    ///
+    /// File should have one item per line, optionally prefixed with its category, like in the outfits list
+    /// (e.g. `S:z8`), or the category's name (e.g. `shirt:z8`). Category can only be omitted if the item ID
+    /// is known to the Multitool and unique across the categories. Empty lines and ones starting with `#` are ignored.
+    /// Items already owned are skipped
+    ImportList {
+        /// Save slot number (0-3)
+        save_slot: u8,
+        /// Path to the text file
+        input_path: PathBuf,
+    },
+}
+
+#[derive(ValueEnum)]
+#[derive(Clone, Copy, Debug)]
+enum ExportFormat {
+    Csv,
+    Md,
+}
+
+pub fn handler(ops: Ops, mut save_dir: SaveDirHandler) -> EResult<()> {
+    log::info!("Working with wardrobe");
+
+    match ops.action {
+        Cmd::Export { save_slot, checklist_format, output_path } => {
+            export_checklist(&mut save_dir, save_slot, checklist_format, output_path.as_deref())
+                .context("Failed to export the checklist")?
+        }
+        Cmd::ImportList { save_slot, input_path } => {
+            import_list(&mut save_dir, save_slot, &input_path).context("Failed to import the list")?
+        }
+    }
+
+    Ok(())
+}
+
+fn export_checklist(
+    save_dir: &mut SaveDirHandler,
+    save_slot: u8,
+    format: ExportFormat,
+    output_path: Option<&Path>,
+) -> EResult<()> {
+    let save = SaveFile::open(save_dir, save_slot)?;
+    let save_data = save.typed_data()?;
+
+    let mut doc = match format {
+        ExportFormat::Csv => format!("{},{},{}\n", tr!("header-category"), tr!("header-item"), tr!("header-status")),
+        ExportFormat::Md => format!("# {}\n", tr!("title-wardrobe-checklist")),
+    };
+
+    for ((name, label), owned) in COSMETICS_LISTS.into_iter().zip(save_data.cosmetics()) {
+        // owned items the Multitool doesn't know about yet go last
+        let mut items: Vec<(&str, bool)> = known::cosmetics(name)
+            .iter()
+            .map(|item| (*item, owned.iter().any(|o| o == item)))
+            .collect();
+        items.extend(
+            owned
+                .iter()
+                .filter(|o| !known::cosmetics(name).contains(&o.as_str()))
+                .map(|o| (o.as_str(), true)),
+        );
+
+        let label = tr!(&format!("category-{}", label.to_lowercase()));
+
+        if let ExportFormat::Md = format {
+            let have = items.iter().filter(|(_, owned)| *owned).count();
+
+            writeln!(doc, "\n## {label} ({have}/{})\n", items.len())?;
+        }
+
+        for (item, owned) in items {
+            match format {
+                ExportFormat::Csv => {
+                    writeln!(doc, "{label},{item},{}", tr!(if owned { "status-owned" } else { "status-missing" }))?
+                }
+                ExportFormat::Md => writeln!(doc, "- [{}] {item}", if owned { 'x' } else { ' ' })?,
+            }
+        }
+    }
+
+    match output_path {
+        Some(path) => {
+            if utils::file_exists(path) && !utils::confirm(&tr!("confirm-overwrite-file", path = path.display()))? {
+                return Err(Failure::WriteRefused.error("Cancelled"));
+            }
+
+            log::info!("Writing checklist to {}", path.display());
+            utils::write_file(path, doc.into_bytes()).context("Failed to write output file")?;
+        }
+        None => print!("{doc}"),
+    }
+
+    Ok(())
+}
+
+/// Short category labels used by the outfits list, in the same order as [`COSMETICS_LISTS`]
+const SHORT_LABELS: [&str; 5] = ["H", "F", "A", "S", "J"];
+
+fn import_list(save_dir: &mut SaveDirHandler, save_slot: u8, input_path: &Path) -> EResult<()> {
+    log::info!("Reading list {}", input_path.display());
+
+    let input = utils::read_file(input_path).context("Failed to read the list")?;
+    let input = String::from_utf8(input).context("List is not valid UTF-8")?;
+
+    let mut save = SaveFile::open(save_dir, save_slot)?;
+    let mut save_data = save.typed_data()?;
+
+    let mut added = 0;
+
+    for (i, line) in input.lines().enumerate() {
+        let line = line.trim();
+
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let (name, item) = match resolve_item(line) {
+            Ok(found) => found,
+            Err(reason) => {
+                log::warn!("Line {}: {reason}, skipping", i + 1);
+                continue;
+            }
+        };
+
+        let (_, list) = COSMETICS_LISTS
+            .iter()
+            .zip(save_data.cosmetics_mut())
+            .find(|((list_name, _), _)| *list_name == name)
+            .expect("Items are only resolved to the cosmetics lists");
+
+        if list.iter().any(|owned| owned == item) {
+            log::info!("{name}: {item} is already owned");
+            continue;
+        }
+
+        log::info!("{name}: unlocking {item}");
+        list.push(item.to_string());
+        added += 1;
+    }
+
+    if added == 0 {
+        log::info!("Nothing to unlock");
+        return Ok(());
+    }
+
+    save.set_typed_data(save_data)?;
+    save.write()?;
+
+    log::info!("Unlocked {added} items");
+
+    Ok(())
+}
+
+/// Find the list the item from an import line belongs to
+///
+/// Known items are far from complete, so with a category any item is taken. Without one, the item has to be known,
+/// to tell which list it goes to
+fn resolve_item(line: &str) -> Result<(&'static str, &str), String> {
+    let (category, item) = match line.split_once(':') {
+        Some((category, item)) => (Some(category.trim()), item.trim()),
+        None => (None, line),
+    };
+
+    if item.is_empty() {
+        return Err("item ID is missing".to_string());
+    }
+
+    if let Some(category) = category {
+        let ((name, _), _) = COSMETICS_LISTS
+            .iter()
+            .zip(SHORT_LABELS)
+            .find(|((name, label), short)| {
+                category == *short || category == *name || category.eq_ignore_ascii_case(label)
+            })
+            .ok_or_else(|| format!("\"{category}\" is not a wardrobe category"))?;
+
+        if !known::cosmetics(name).contains(&item) {
+            log::warn!("{name}: {item} is not a known item, unlocking anyway");
+        }
+
+        return Ok((name, item));
+    }
+
+    let mut found = COSMETICS_LISTS
+        .iter()
+        .map(|(name, _)| *name)
+        .filter(|name| known::cosmetics(name).contains(&item));
+
+    match (found.next(), found.next()) {
+        (Some(name), None) => Ok((name, item)),
+        (Some(_), Some(_)) => {
+            Err(format!("\"{item}\" exists in multiple categories, prefix it with one (like `S:{item}`)"))
+        }
+        _ => Err(format!("\"{item}\" is not a known item, prefix it with a category (like `S:{item}`)")),
+    }
+}
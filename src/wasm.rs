@@ -0,0 +1,135 @@
+//! Functions exported from the WebAssembly build, for a browser-based save editor
+//!
+//! These are plain exports, without wasm-bindgen glue. Buffers are passed through the module's memory: JS allocates
+//! the input with [`hc_alloc`], and each function returns its output as a pointer and length packed into one `u64`
+//! (pointer in the high half), which has to be released with [`hc_free`] once read. If the function failed it returns
+//! 0 instead, and the error message can be taken with [`hc_error`] the same way.
+//!
+//! Saves are handled in a [`MemoryFs`], since there is no filesystem to read them from in the browser
+
+use eyre::Context;
+use std::cell::RefCell;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::converter;
+use crate::organiser;
+use crate::save::SaveFile;
+use crate::utils;
+use crate::vfs::{self, Fs, MemoryFs};
+
+/// Where the save given to a function is put in the memory filesystem
+const SAVE_PATH: &str = "/savefile0.json";
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// Allocate a buffer of `len` bytes for the input of a function
+#[no_mangle]
+pub extern "C" fn hc_alloc(len: usize) -> *mut u8 {
+    Box::into_raw(vec![0u8; len].into_boxed_slice()).cast()
+}
+
+/// Release a buffer from [`hc_alloc`], or one returned by another function
+///
+/// # Safety
+///
+/// `ptr` and `len` have to be exactly those of the buffer, and it can't be used afterward
+#[no_mangle]
+pub unsafe extern "C" fn hc_free(ptr: *mut u8, len: usize) {
+    drop(Box::from_raw(std::ptr::slice_from_raw_parts_mut(ptr, len)));
+}
+
+/// Message of the last error, as a packed buffer. 0 if there was none
+#[no_mangle]
+pub extern "C" fn hc_error() -> u64 {
+    match LAST_ERROR.with(|err| err.borrow_mut().take()) {
+        Some(message) => pack(message.into_bytes()),
+        None => 0,
+    }
+}
+
+/// Convert an old binary save into the JSON one
+///
+/// # Safety
+///
+/// `ptr` and `len` have to be those of a buffer from [`hc_alloc`]
+#[no_mangle]
+pub unsafe extern "C" fn hc_convert(ptr: *const u8, len: usize) -> u64 {
+    let mut input = std::slice::from_raw_parts(ptr, len);
+
+    respond(|| {
+        let json = converter::convert(&mut input)?;
+
+        Ok(serde_json::to_vec_pretty(&json).context("Failed to serialize JSON")?)
+    })
+}
+
+/// Sort the lists and deduplicate the emails of a JSON save, same as the `organise` command
+///
+/// # Safety
+///
+/// `ptr` and `len` have to be those of a buffer from [`hc_alloc`]
+#[no_mangle]
+pub unsafe extern "C" fn hc_organise(ptr: *const u8, len: usize) -> u64 {
+    let input = std::slice::from_raw_parts(ptr, len);
+
+    respond(|| {
+        let fs = memory_fs();
+        fs.insert(SAVE_PATH, input);
+
+        let mut save = SaveFile::open_path(PathBuf::from(SAVE_PATH))?;
+        let mut save_data = save.typed_data()?;
+
+        organiser::organise(&mut save_data);
+
+        save.set_typed_data(save_data)?;
+        save.write()?;
+
+        read_back(&fs, Path::new(SAVE_PATH))
+    })
+}
+
+/// Memory filesystem for the saves, set up on the first call
+fn memory_fs() -> Arc<MemoryFs> {
+    thread_local! {
+        static FS: Arc<MemoryFs> = {
+            let fs = Arc::new(MemoryFs::new());
+            vfs::set(fs.clone());
+
+            fs
+        };
+    }
+
+    FS.with(Arc::clone)
+}
+
+fn read_back(fs: &MemoryFs, path: &Path) -> crate::Result<Vec<u8>> {
+    let data = fs.read(path).context("Failed to read the written save")?;
+
+    // nothing else is going to read the files, no reason to keep them
+    utils::remove_file(path)?;
+    utils::remove_file(&utils::with_added_extension(path, "bak"))?;
+
+    Ok(data)
+}
+
+fn respond(f: impl FnOnce() -> crate::Result<Vec<u8>>) -> u64 {
+    match f() {
+        Ok(output) => pack(output),
+        Err(err) => {
+            LAST_ERROR.with(|last| *last.borrow_mut() = Some(format!("{:#}", eyre::Report::from(err))));
+
+            0
+        }
+    }
+}
+
+/// Leak the buffer, to be released by JS with [`hc_free`]
+fn pack(data: Vec<u8>) -> u64 {
+    let len = data.len() as u64;
+    let ptr = Box::into_raw(data.into_boxed_slice()) as *mut u8 as usize as u64;
+
+    (ptr << 32) | len
+}
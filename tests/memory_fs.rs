@@ -0,0 +1,203 @@
+//! Commands of the library run against saves that only exist in a `MemoryFs`
+
+use hc_multitool::exit::Failure;
+use hc_multitool::organiser;
+use hc_multitool::outfits::{Outfit, OutfitsStorage};
+use hc_multitool::save::{PartialSave, SaveFile, Wardrobe};
+use hc_multitool::utils::{self, SaveDirHandler};
+use hc_multitool::vfs::{self, Fs, MemoryFs};
+use serde_json::{json, Value};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::SystemTime;
+
+const SAVE_DIR: &str = "/saves";
+
+/// Filesystem is global, so only one test can use it at a time
+static FS_LOCK: Mutex<()> = Mutex::new(());
+
+/// Fresh filesystem with the saves in their slots, in use until the guard is dropped
+fn memory_fs(saves: &[(u8, &str)]) -> (Arc<MemoryFs>, MutexGuard<'static, ()>) {
+    let guard = FS_LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+    let fs = Arc::new(MemoryFs::new());
+
+    for (slot, save) in saves {
+        fs.insert(slot_path(*slot), *save);
+    }
+
+    vfs::set(fs.clone());
+
+    (fs, guard)
+}
+
+fn slot_path(slot: u8) -> PathBuf {
+    Path::new(SAVE_DIR).join(format!("savefile{slot}.json"))
+}
+
+fn save_dir() -> SaveDirHandler {
+    SaveDirHandler::new_override(Some(PathBuf::from(SAVE_DIR)))
+}
+
+fn read_json(fs: &MemoryFs, path: &Path) -> Value {
+    serde_json::from_slice(&fs.read(path).unwrap()).unwrap()
+}
+
+const SAVE: &str = r#"{"version": 1, "save_data_key": {
+  "hairon": "k", "faceon": "aa", "jewlon": "a", "shirton": "z8", "jacketon": "v3",
+  "hairlist": ["k", "a"], "facelist": ["aa"], "jewllist": ["a"], "shirtlist": ["z8", "a"], "jacketlist": ["v3", "a"],
+  "furnlist": [{"name": "bed1"}, {"name": "computer1"}],
+  "emailreadlist": [2, 1, 2], "emailunreadlist": [],
+  "unknown": 1e3
+}}"#;
+
+#[test]
+fn organise_writes_to_memory_and_keeps_backup() {
+    let (fs, _guard) = memory_fs(&[(0, SAVE)]);
+
+    let mut save = SaveFile::open(&mut save_dir(), 0).unwrap();
+    let mut save_data = save.typed_data().unwrap();
+    organiser::organise(&mut save_data);
+    save.set_typed_data(save_data).unwrap();
+    save.write().unwrap();
+
+    let data = &read_json(&fs, &slot_path(0))["save_data_key"];
+    assert_eq!(data["hairlist"], json!(["a", "k"]));
+    assert_eq!(data["furnlist"], json!([{"name": "computer1"}, {"name": "bed1"}]));
+    assert_eq!(data["emailreadlist"], json!([1, 2]));
+
+    let backup = fs.read(&slot_path(0).with_extension("json.bak")).unwrap();
+    assert_eq!(backup, SAVE.as_bytes());
+
+    // unchanged values are written back as they were read
+    let written = String::from_utf8(fs.read(&slot_path(0)).unwrap()).unwrap();
+    assert!(written.contains("1e3"), "{written}");
+}
+
+#[test]
+fn organise_slots_lists_only_existing_ones() {
+    let (_fs, _guard) = memory_fs(&[(0, SAVE), (2, SAVE)]);
+
+    assert_eq!(organiser::existing_slots(&mut save_dir()).unwrap(), [0, 2]);
+}
+
+#[test]
+fn missing_slot_is_not_found() {
+    let (_fs, _guard) = memory_fs(&[(0, SAVE)]);
+
+    let err = SaveFile::open(&mut save_dir(), 1).err().unwrap();
+
+    assert_eq!(err.kind(), Some(Failure::SaveNotFound));
+}
+
+#[test]
+fn outfit_saved_from_one_slot_loads_into_another() {
+    let other = SAVE.replace(r#""hairon": "k""#, r#""hairon": "a""#);
+    let (fs, _guard) = memory_fs(&[(0, SAVE), (1, &other)]);
+    let outfits_path = Path::new(SAVE_DIR).join("outfits.json");
+
+    let source = PartialSave::open(&mut save_dir(), 0, &Wardrobe::KEYS).unwrap();
+    let mut storage = OutfitsStorage::read(&outfits_path).unwrap();
+    storage.outfits.insert("casual".to_string(), Outfit::worn(&source.typed_data().unwrap()));
+    storage.write(&outfits_path).unwrap();
+
+    let storage = OutfitsStorage::read(&outfits_path).unwrap();
+    let outfit = storage.outfits.into_values().next().unwrap();
+
+    let mut target = PartialSave::open(&mut save_dir(), 1, &Wardrobe::KEYS).unwrap();
+    let mut wardrobe: Wardrobe = target.typed_data().unwrap();
+    outfit.put_on(&mut wardrobe, false).unwrap();
+    target.set_typed_data(wardrobe).unwrap();
+    target.write().unwrap();
+
+    let data = &read_json(&fs, &slot_path(1))["save_data_key"];
+    assert_eq!(data["hairon"], "k");
+    assert_eq!(data["unknown"], json!(1000.0));
+    assert!(fs.written().contains(&outfits_path));
+}
+
+#[test]
+fn outfit_with_items_not_owned_is_refused() {
+    let (_fs, _guard) = memory_fs(&[(0, SAVE)]);
+
+    let save = PartialSave::open(&mut save_dir(), 0, &Wardrobe::KEYS).unwrap();
+    let mut wardrobe: Wardrobe = save.typed_data().unwrap();
+    let outfit = Outfit { hair: Some("zz".to_string()), face: None, accessory: None, shirt: None, jacket: None };
+
+    let err = outfit.put_on(&mut wardrobe, false).err().unwrap();
+
+    assert_eq!(err.kind(), Some(Failure::NotFound));
+    assert_eq!(wardrobe.hair_worn, "k");
+}
+
+#[test]
+fn failed_commit_leaves_every_file_as_it_was() {
+    let (memory, _guard) = memory_fs(&[(0, SAVE)]);
+    let added = Path::new(SAVE_DIR).join("added.json");
+    vfs::set(Arc::new(FailingRename { inner: memory.clone(), to: added.clone() }));
+
+    utils::begin_transaction();
+    utils::replace_file(&slot_path(0), b"{}".to_vec()).unwrap();
+    utils::write_file(&added, b"{}".to_vec()).unwrap();
+
+    assert!(utils::commit_transaction().is_err());
+
+    assert_eq!(memory.read(&slot_path(0)).unwrap(), SAVE.as_bytes());
+    assert_eq!(memory.written(), [slot_path(0)]);
+}
+
+/// Filesystem that can't rename anything into one path
+struct FailingRename {
+    inner: Arc<MemoryFs>,
+    to: PathBuf,
+}
+
+impl Fs for FailingRename {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        self.inner.read(path)
+    }
+
+    fn write_atomic(&self, path: &Path, data: &[u8]) -> io::Result<()> {
+        self.inner.write_atomic(path, data)
+    }
+
+    fn append(&self, path: &Path, data: &[u8]) -> io::Result<()> {
+        self.inner.append(path, data)
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        if to == self.to {
+            return Err(io::Error::other("disk full"));
+        }
+
+        self.inner.rename(from, to)
+    }
+
+    fn remove(&self, path: &Path) -> io::Result<()> {
+        self.inner.remove(path)
+    }
+
+    fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+        self.inner.create_dir_all(path)
+    }
+
+    fn list_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+        self.inner.list_dir(path)
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        self.inner.exists(path)
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        self.inner.is_dir(path)
+    }
+
+    fn size(&self, path: &Path) -> io::Result<u64> {
+        self.inner.size(path)
+    }
+
+    fn modified(&self, path: &Path) -> io::Result<SystemTime> {
+        self.inner.modified(path)
+    }
+}